@@ -0,0 +1,86 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use anyhow::Context;
+use quelle_engine::{cache::ModuleCache, client::HttpConfig};
+use serde::Deserialize;
+
+/// The settings of the client, read from a toml file
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// The `[http]` table, see [HttpConfig] for its keys
+    pub http: HttpConfig,
+    /// Keep the compiled extensions in the user cache directory
+    pub module_cache: bool,
+}
+
+impl ClientConfig {
+    /// Read the config, falling back to the defaults when the file does not exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text)
+                .with_context(|| format!("failed to parse config '{}'", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("failed to read config '{}'", path.display())),
+        }
+    }
+
+    /// The module cache in the user cache directory, when enabled
+    pub fn module_cache(&self) -> Option<ModuleCache> {
+        self.module_cache.then(ModuleCache::user).flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_http_table() {
+        let config = toml::from_str::<ClientConfig>(
+            r#"
+            module_cache = true
+
+            [http]
+            user_agent = "quelle"
+            timeout = 5000
+            proxy = "socks5h://127.0.0.1:9050"
+            brotli = false
+
+            [http.headers]
+            Accept-Language = "en"
+
+            [http.rate_limit]
+            interval = 500
+
+            [http.rate_limits."www.royalroad.com"]
+            interval = 2000
+            concurrency = 1
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.module_cache);
+        assert_eq!(config.http.user_agent, "quelle");
+        assert_eq!(config.http.timeout, Some(5000));
+        assert_eq!(
+            config.http.connect_timeout,
+            HttpConfig::default().connect_timeout
+        );
+        assert!(!config.http.brotli && config.http.gzip);
+        assert_eq!(config.http.headers["Accept-Language"], "en");
+        assert_eq!(config.http.rate_limit.interval, Some(500));
+        assert_eq!(
+            config.http.rate_limits["www.royalroad.com"].concurrency,
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn should_default_without_file() {
+        let config = ClientConfig::load(Path::new("does-not-exist.toml")).unwrap();
+        assert_eq!(config.http, HttpConfig::default());
+        assert!(!config.module_cache);
+        assert!(config.module_cache().is_none());
+    }
+}
@@ -1,23 +1,24 @@
 use std::{
-    fs::{self, File},
-    io::BufWriter,
+    fs,
     path::{Path, PathBuf},
-    thread,
 };
 
 use anyhow::bail;
 use log::info;
-use quelle_core::prelude::{Chapter, ExtensionConfig, Meta};
-use quelle_engine::{data::DefaultImpl, Runtime};
-use quelle_persist::{CoverLoc, EventKind, EventLog, Persist, PersistNovel, SavedNovel};
-use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use quelle_core::prelude::{Chapter, ExtensionConfig, Meta, RateLimit};
+use quelle_engine::{
+    any::AnyRuntime, cookie::CookieStore, data::DefaultImpl, mirror::Mirrors, storage::Storage,
+    Runtime,
+};
+use quelle_persist::{CoverLoc, EventKind, EventLog, Global, Persist, PersistNovel, SavedNovel};
 use url::Url;
 
-use super::DownloadOptions;
+use super::{images, DownloadOptions};
 
 pub struct DownloadHandler<'a> {
-    pub runner: Runtime<DefaultImpl>,
+    pub runner: AnyRuntime<DefaultImpl>,
     pub meta: Meta,
+    pub mirrors: Mirrors,
     pub persist_novel: PersistNovel<'a>,
     pub data: SavedNovel,
     pub options: DownloadOptions,
@@ -31,20 +32,47 @@ impl<'a> DownloadHandler<'a> {
         wasm_path: PathBuf,
         options: DownloadOptions,
     ) -> anyhow::Result<DownloadHandler<'a>> {
-        let mut runner = Runtime::new(&wasm_path).await?;
+        let mut http = options.http.clone();
+        if let Some(delay) = options.delay {
+            let delay = RateLimit {
+                interval: Some(delay.as_millis() as u64),
+                ..Default::default()
+            };
+            http.rate_limit = http.rate_limit.stricter(&delay);
+        }
+
+        let mirrors = Mirrors::default();
+        let data = DefaultImpl::new(&http)?;
+        let mut builder = Runtime::builder();
+        if let Some(cache) = options.module_cache.clone() {
+            builder = builder.module_cache(cache);
+        }
+
+        let mut runner = builder
+            .http_client(data.client.clone())
+            .rate_limiter(http.rate_limiter())
+            .cookie_store(CookieStore::persistent(persist.options.cookies_dir.clone()))
+            .storage(Storage::persistent(persist.options.storage_dir.clone()))
+            .mirrors(mirrors.clone())
+            .build_any(&wasm_path, data)
+            .await?;
         runner
             .setup(&ExtensionConfig {
                 level_filter: log::LevelFilter::Info,
             })
             .await?;
 
+        // Read first so the rate limit hint applies to every request
+        let meta = runner.meta().await?;
+        if let Some(base_url) = persist.read_global()?.mirror(&meta.id) {
+            mirrors.prefer(base_url);
+        }
+
         let novel = runner.fetch_novel(url.as_str()).await?;
         if novel.title.is_empty() {
             bail!("The novel title cannot be empty");
         }
 
-        let meta = runner.meta().await?;
-
         let persist_novel = persist.persist_novel(persist.novel_path(&meta, &novel.title));
         let data = persist_novel
             .read_data()?
@@ -55,6 +83,7 @@ impl<'a> DownloadHandler<'a> {
         Ok(Self {
             runner,
             meta,
+            mirrors,
             persist_novel,
             data,
             log,
@@ -73,6 +102,25 @@ impl<'a> DownloadHandler<'a> {
         Ok(())
     }
 
+    /// Point the stored urls to the mirror the requests went to and remember it
+    ///
+    /// The previous url of the novel keeps leading to it.
+    pub fn rebase_urls(&mut self, global: &mut Global) {
+        let Some(base_url) = self.mirrors.current() else {
+            return;
+        };
+
+        if let Some(previous) = self.data.rebase_urls(&self.meta, &base_url) {
+            let url = &self.data.novel.url;
+            info!("Moved the novel from '{previous}' to '{url}'.");
+
+            global.insert_novel(url.clone(), self.persist_novel.dir().to_path_buf());
+            global.alias_novel(previous, url);
+        }
+
+        global.set_mirror(self.meta.id.clone(), base_url);
+    }
+
     pub async fn download(&mut self) -> anyhow::Result<()> {
         let chapter_dir = self.persist_novel.chapters_dir();
         if !chapter_dir.exists() {
@@ -99,7 +147,6 @@ impl<'a> DownloadHandler<'a> {
             &mut self.log,
             &chapters,
             self.persist_novel.dir(),
-            &self.options,
         )
         .await?;
 
@@ -107,13 +154,12 @@ impl<'a> DownloadHandler<'a> {
     }
 
     async fn download_chapters(
-        runner: &mut Runtime<DefaultImpl>,
+        runner: &mut AnyRuntime<DefaultImpl>,
         persist_novel: &PersistNovel<'a>,
         data: &SavedNovel,
         log: &mut EventLog,
         chapters: &[&Chapter],
         save_dir: &Path,
-        options: &DownloadOptions,
     ) -> anyhow::Result<()> {
         for chapter in chapters {
             if let Some(path) = data.downloaded.get(&chapter.url) {
@@ -122,12 +168,9 @@ impl<'a> DownloadHandler<'a> {
                 }
             }
 
-            if let Some(delay) = &options.delay {
-                thread::sleep(*delay);
-            }
-
             let content = runner.fetch_chapter_content(&chapter.url).await?;
-            let path = persist_novel.save_chapter(chapter, content.data)?;
+            let content = images::save_images(runner, persist_novel, chapter, content.data).await?;
+            let path = persist_novel.save_chapter(chapter, content)?;
 
             info!("Downloaded '{}' to '{}'.", &chapter.title, path.display());
 
@@ -141,39 +184,21 @@ impl<'a> DownloadHandler<'a> {
         Ok(())
     }
 
-    pub fn download_cover(&mut self) -> anyhow::Result<()> {
+    pub async fn download_cover(&mut self) -> anyhow::Result<()> {
         let data = &mut self.data;
-        let Some(url) = data.novel.cover.as_ref() else { return Ok(()) };
-
-        let client = Client::builder()
-            .user_agent(
-                "Mozilla/5.0 (X11; Fedora; Linux x86_64; rv:107.0) Gecko/20100101 Firefox/107.0",
-            )
-            .build()?;
-
-        let mut response = client.get(url).send()?;
-        if !response.status().is_success() {
-            let status = response.status();
-            bail!("Cover download failed with {}", status.as_str());
-        }
+        let Some(url) = data.novel.cover.as_ref() else {
+            return Ok(());
+        };
 
+        let response = self.runner.fetch_image(url).await?;
         info!("Downloaded novel cover from '{url}'.");
 
-        let content_type = response
-            .headers()
-            .get(CONTENT_TYPE)
-            .map(|value| value.to_str().ok())
-            .flatten()
-            .map(|value| value.to_owned())
-            .unwrap_or_default();
-
+        let content_type = images::content_type(&response);
         info!("Content type from headers: {content_type}");
 
-        let suffix = mime_guess::get_mime_extensions_str(&content_type).map(|exts| exts[0]);
+        let suffix = images::file_suffix(&content_type);
         let path = self.persist_novel.cover_path(suffix);
-
-        let mut file = BufWriter::new(File::create(&path)?);
-        response.copy_to(&mut file)?;
+        fs::write(&path, response.body.unwrap_or_default())?;
 
         info!("Saved novel cover to '{}'.", path.display());
         data.cover = Some(CoverLoc { path, content_type });
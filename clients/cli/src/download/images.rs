@@ -0,0 +1,99 @@
+use kuchiki::traits::TendrilSink;
+use log::{info, warn};
+use quelle_core::prelude::{Chapter, Response};
+use quelle_engine::{any::AnyRuntime, data::DefaultImpl};
+use quelle_persist::PersistNovel;
+use url::Url;
+
+/// The file extension for the content type of a response, if it is known
+pub fn file_suffix(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime_guess::get_mime_extensions_str(mime).map(|exts| exts[0])
+}
+
+pub fn content_type(response: &Response) -> String {
+    response
+        .header("content-type")
+        .map(|value| value.to_owned())
+        .unwrap_or_default()
+}
+
+/// Download the images of the chapter content, pointing them to the saved files
+///
+/// Images which fail to download keep their original source, so the content
+/// is saved either way.
+pub async fn save_images(
+    runner: &mut AnyRuntime<DefaultImpl>,
+    persist_novel: &PersistNovel<'_>,
+    chapter: &Chapter,
+    content: String,
+) -> anyhow::Result<String> {
+    let document = kuchiki::parse_html().one(content.as_str());
+    let images = match document.select("img[src]") {
+        Ok(images) => images.collect::<Vec<_>>(),
+        Err(_) => return Ok(content),
+    };
+
+    if images.is_empty() {
+        return Ok(content);
+    }
+
+    let base = Url::parse(&chapter.url).ok();
+    for (index, image) in images.iter().enumerate() {
+        let src = image.attributes.borrow().get("src").map(str::to_owned);
+        let Some(src) = src else { continue };
+
+        let url = match &base {
+            Some(base) => base.join(&src),
+            None => Url::parse(&src),
+        };
+        let url = match url {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+            // Inline data and unreadable sources are left as they are
+            _ => continue,
+        };
+
+        let response = match runner.fetch_image(url.as_str()).await {
+            Ok(response) => response,
+            Err(error) => {
+                warn!("Failed to download the image '{url}': {error}");
+                continue;
+            }
+        };
+
+        let name = match file_suffix(&content_type(&response)) {
+            Some(suffix) => format!("{}-{index}.{suffix}", chapter.index),
+            None => format!("{}-{index}", chapter.index),
+        };
+        let path = persist_novel.save_image(&name, response.body.as_deref().unwrap_or_default())?;
+        info!("Saved image '{url}' to '{}'.", path.display());
+
+        // Chapters are saved next to the images directory
+        image
+            .attributes
+            .borrow_mut()
+            .insert("src", format!("../images/{name}"));
+    }
+
+    let body = document
+        .select_first("body")
+        .map_err(|_| anyhow::anyhow!("The chapter content has no body"))?;
+
+    let mut html = Vec::new();
+    for child in body.as_node().children() {
+        child.serialize(&mut html)?;
+    }
+    Ok(String::from_utf8(html)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ignore_content_type_parameters() {
+        assert_eq!(file_suffix("image/png"), Some("png"));
+        assert_eq!(file_suffix("image/png; charset=binary"), Some("png"));
+        assert_eq!(file_suffix(""), None);
+    }
+}
@@ -1,4 +1,5 @@
 mod handler;
+mod images;
 mod options;
 
 use std::path::PathBuf;
@@ -27,10 +28,10 @@ pub async fn download(
     match &handler.options.cover {
         CoverAction::Dynamic => {
             if !handler.data.is_cover_downloaded() {
-                download_cover_and_warn(&mut handler)?;
+                download_cover_and_warn(&mut handler).await?;
             }
         }
-        CoverAction::Force => download_cover_and_warn(&mut handler)?,
+        CoverAction::Force => download_cover_and_warn(&mut handler).await?,
         CoverAction::Ignore => (),
     }
 
@@ -40,11 +41,16 @@ pub async fn download(
     handler.download().await?;
     handler.save()?;
 
+    // The downloaded chapters are committed, so their urls move as well
+    handler.rebase_urls(&mut global);
+    handler.save()?;
+    persist.save_global(&global)?;
+
     Ok(handler.data)
 }
 
-fn download_cover_and_warn(handler: &mut DownloadHandler) -> Result<(), anyhow::Error> {
-    match handler.download_cover() {
+async fn download_cover_and_warn(handler: &mut DownloadHandler<'_>) -> Result<(), anyhow::Error> {
+    match handler.download_cover().await {
         Ok(_) => handler.save(),
         Err(error) => {
             warn!("{error}");
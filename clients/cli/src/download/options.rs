@@ -1,5 +1,7 @@
 use std::{ops::RangeInclusive, path::PathBuf, time::Duration};
 
+use quelle_engine::{cache::ModuleCache, client::HttpConfig};
+
 use crate::args::CoverAction;
 
 #[derive(Debug)]
@@ -8,6 +10,9 @@ pub struct DownloadOptions {
     pub range: Option<RangeInclusive<usize>>,
     pub delay: Option<Duration>,
     pub cover: CoverAction,
+    pub http: HttpConfig,
+    /// Load the compiled extension from the cache
+    pub module_cache: Option<ModuleCache>,
 }
 
 impl Default for DownloadOptions {
@@ -17,6 +22,8 @@ impl Default for DownloadOptions {
             range: Default::default(),
             delay: Default::default(),
             cover: Default::default(),
+            http: Default::default(),
+            module_cache: Default::default(),
         }
     }
 }
@@ -1,5 +1,6 @@
 mod args;
 mod bundle;
+mod config;
 mod download;
 
 use std::{
@@ -13,9 +14,10 @@ use std::{
 use anyhow::{anyhow, bail};
 use args::{CoverAction, DownloadRange};
 use clap::{Parser, Subcommand};
+use config::ClientConfig;
 use download::DownloadOptions;
 use log::{info, warn};
-use quelle_engine::Runtime;
+use quelle_engine::{any::AnyRuntime, capabilities::Trait, cookie::CookieStore};
 use quelle_lock::Lock;
 use quelle_persist::{create_parent_all, Persist, PersistOptions};
 use simplelog::{Config, LevelFilter, TermLogger};
@@ -34,6 +36,10 @@ struct Cli {
     #[clap(short, long, default_value = "data")]
     data_dir: PathBuf,
 
+    /// The toml file with the settings of the client
+    #[clap(long, default_value = "quelle.toml")]
+    config: PathBuf,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -58,7 +64,7 @@ enum Commands {
         #[arg(short, long)]
         range: Option<DownloadRange>,
 
-        /// Delay between each chapter download in milliseconds
+        /// Minimum delay between requests to the same site in milliseconds
         #[arg(short, long)]
         delay: Option<u32>,
 
@@ -79,6 +85,16 @@ enum Commands {
     Bundle {
         url: Url,
     },
+
+    /// Show the saved cookies of an extension
+    Cookies {
+        /// The id of the extension
+        id: String,
+
+        /// Remove the saved cookies instead
+        #[arg(long)]
+        clear: bool,
+    },
 }
 
 #[tokio::main]
@@ -105,6 +121,8 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn run(cli: Cli) -> anyhow::Result<()> {
+    let config = ClientConfig::load(&cli.config)?;
+
     match cli.command {
         Commands::Detect { url } => {
             let lock = Lock::open(&cli.lock_file)?;
@@ -121,7 +139,7 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             }
         }
         Commands::Lock { dir } => {
-            let lock = Lock::generate(&dir).await?;
+            let lock = Lock::generate(&dir, config.module_cache().as_ref()).await?;
             lock.save(&cli.lock_file)?;
             info!("Saved lock file to '{}'", cli.lock_file.display());
         }
@@ -144,6 +162,8 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 range: range.map(|r| r.0),
                 delay: delay.map(|v| Duration::from_millis(v as u64)),
                 cover,
+                module_cache: config.module_cache(),
+                http: config.http,
             };
 
             download::download(persist, url, PathBuf::from(&extension.path), options).await?;
@@ -155,7 +175,17 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 exit(1);
             };
 
-            let mut runner = Runtime::new(Path::new(&extension.path)).await?;
+            let supported = extension
+                .capabilities
+                .as_ref()
+                .map(|capabilities| capabilities.supports(Trait::Popular));
+            if supported == Some(false) {
+                log::error!("'{}' does not support popular browse", extension.name);
+                exit(1);
+            }
+
+            let path = Path::new(&extension.path);
+            let mut runner = AnyRuntime::new(path, &config.http, config.module_cache()).await?;
             let meta = runner.meta().await?;
 
             if !runner.popular_supported() {
@@ -191,7 +221,7 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                     bail!("The wasm extension file could not be found");
                 }
 
-                let mut runner = Runtime::new(path).await?;
+                let mut runner = AnyRuntime::new(path, &config.http, config.module_cache()).await?;
                 let meta = runner.meta().await?;
                 info!("Acquired source meta information from wasm file.");
 
@@ -217,6 +247,22 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             bundle::compile_epub(meta, data, path.to_path_buf(), &mut file)
                 .map_err(|e| anyhow!("failed to bundle epub: {}", e.to_string()))?;
         }
+        Commands::Cookies { id, clear } => {
+            let persist = Persist::new(PersistOptions::default());
+            let jar = CookieStore::persistent(persist.options.cookies_dir).jar(&id);
+
+            if clear {
+                jar.clear();
+                info!("Cleared the cookies of '{id}'");
+            } else {
+                for cookie in jar.cookies() {
+                    println!(
+                        "{}={} ({}{})",
+                        cookie.name, cookie.value, cookie.domain, cookie.path
+                    );
+                }
+            }
+        }
     }
 
     Ok(())
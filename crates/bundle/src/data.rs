@@ -24,6 +24,9 @@ pub trait Bundle {
 
     /// Return chapter content when the url of the chapter is provided
     fn chapter_content(&self, url: &str) -> Result<Option<String>, Box<dyn std::error::Error>>;
+
+    /// The paths to the images the chapter content refers to as `../images/<file name>`
+    fn image_paths(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>>;
 }
 
 ///
@@ -61,4 +64,21 @@ impl Bundle for PersistBundle {
         info!("Read chapter content from '{}'.", file_path.display());
         Ok(Some(content))
     }
+
+    fn image_paths(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = self.base_path.join("images");
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
 }
@@ -52,6 +52,10 @@ pub fn bundle_epub<B: Bundle>(
 
     info!("Written novel preface");
 
+    for path in bundle.image_paths()? {
+        add_image(&mut builder, &path)?;
+    }
+
     for volume in &novel.volumes {
         for chapter in &volume.chapters {
             let file_name = format!("chapters/{}.xhtml", &chapter.index);
@@ -111,6 +115,21 @@ fn set_cover_image(
     Ok(())
 }
 
+fn add_image(
+    builder: &mut EpubBuilder<ZipLibrary>,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(name) = path.file_name() else { return Ok(()) };
+    let name = name.to_string_lossy();
+    let content_type = mime_guess::from_path(path).first_or_octet_stream();
+
+    let file = File::open(path)?;
+    builder.add_resource(format!("images/{name}"), file, content_type.essence_str())?;
+    info!("Written image file '{}'", path.display());
+
+    Ok(())
+}
+
 fn capitalize(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -5,6 +5,7 @@ use std::{
     path::{Path, PathBuf},
     process::{Command, Stdio},
 };
+use wit_component::ComponentEncoder;
 
 #[derive(Deserialize, Debug)]
 struct RootCargo {
@@ -26,10 +27,20 @@ struct Package {
     name: String,
 }
 
-pub fn build(extension: Option<PathBuf>, out: PathBuf, release: bool) -> anyhow::Result<()> {
+pub fn build(
+    extension: Option<PathBuf>,
+    out: PathBuf,
+    release: bool,
+    component: bool,
+) -> anyhow::Result<()> {
     match extension {
         Some(path) => {
-            build_extension(&path.as_os_str().to_string_lossy(), &out, release)?;
+            build_extension(
+                &path.as_os_str().to_string_lossy(),
+                &out,
+                release,
+                component,
+            )?;
         }
         None => {
             let members = {
@@ -43,7 +54,7 @@ pub fn build(extension: Option<PathBuf>, out: PathBuf, release: bool) -> anyhow:
             let extensions = members.iter().filter(|v| v.starts_with("extensions/"));
 
             for extension in extensions {
-                build_extension(extension, &out, release)?
+                build_extension(extension, &out, release, component)?
             }
         }
     }
@@ -51,7 +62,7 @@ pub fn build(extension: Option<PathBuf>, out: PathBuf, release: bool) -> anyhow:
     Ok(())
 }
 
-fn build_extension(path: &str, out: &Path, release: bool) -> anyhow::Result<()> {
+fn build_extension(path: &str, out: &Path, release: bool, component: bool) -> anyhow::Result<()> {
     let package_name = {
         let path = Path::new(path).join("Cargo.toml");
         let content = fs::read_to_string(path)?;
@@ -71,6 +82,10 @@ fn build_extension(path: &str, out: &Path, release: bool) -> anyhow::Result<()>
         args.push("--release");
     }
 
+    if component {
+        args.extend(["--features", "quelle_glue/component"]);
+    }
+
     let mut command = Command::new("cargo")
         .args(args)
         .stdout(Stdio::inherit())
@@ -83,8 +98,19 @@ fn build_extension(path: &str, out: &Path, release: bool) -> anyhow::Result<()>
     let mode = if release { "release" } else { "debug" };
     let path = format!("target/wasm32-unknown-unknown/{mode}/{package_name}.wasm");
     let to = out.join(format!("{package_name}.wasm"));
-    fs::rename(&path, &to)
-        .with_context(|| format!("failed to move {} to {}", path, to.display()))?;
+    if component {
+        // The module only holds the WIT world in a custom section, the
+        // component wrapping it is what the engine instantiates
+        let module = fs::read(&path).with_context(|| format!("failed to read {path}"))?;
+        let component = ComponentEncoder::default()
+            .module(&module)
+            .and_then(|encoder| encoder.validate(true).encode())
+            .with_context(|| format!("failed to encode '{package_name}' as a component"))?;
+        fs::write(&to, component).with_context(|| format!("failed to write {}", to.display()))?;
+    } else {
+        fs::rename(&path, &to)
+            .with_context(|| format!("failed to move {} to {}", path, to.display()))?;
+    }
 
     Ok(())
 }
@@ -1,57 +1,52 @@
-use std::{error, fs, future::Future, path::PathBuf};
+use std::{error, fs, path::PathBuf};
 
-use quelle_engine::module::{
-    http::{parse_response, read_request, send_request_reqwest},
-    utils::write_str,
+use async_trait::async_trait;
+use quelle_core::{
+    prelude::{Request, RequestError, Response},
+    transport,
 };
+use quelle_engine::client::{ConfiguredClient, HttpClient, HttpConfig};
 use slug::slugify;
-use wasmtime::Caller;
 
-pub struct CachingImpl {
-    pub client: reqwest::Client,
+/// Answers the requests from the cache, sending and caching them when missing
+pub struct CachingClient {
+    pub client: ConfiguredClient,
     pub cache: Cache,
 }
 
-impl CachingImpl {
-    pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::builder()
-                .user_agent("Mozilla/5.0 (X11; Fedora; Linux x86_64; rv:107.0) Gecko/20100101 Firefox/107.0")
-                .build()
-                .unwrap(),
+impl CachingClient {
+    pub fn new(http: &HttpConfig) -> quelle_engine::error::Result<Self> {
+        Ok(Self {
+            client: http.client()?,
             cache: Cache::default(),
-        }
+        })
     }
 }
 
-pub fn send_request<'a>(
-    mut caller: Caller<'a, CachingImpl>,
-    ptr: i32,
-    len: i32,
-) -> Box<dyn Future<Output = i32> + Send + 'a> {
-    Box::new(async move {
-        let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-        let request = read_request(&mut caller, ptr, len, &memory);
-
-        let cache = caller.data().cache.get(&request.url).unwrap();
-        let json = cache.map(|data| String::from_utf8(data).ok()).flatten();
-
-        let json = if let Some(json) = json {
-            json
-        } else {
-            let key = request.url.clone();
-            let client = &caller.data().client;
+#[async_trait]
+impl HttpClient for CachingClient {
+    async fn send(&self, request: Request) -> Result<Response, RequestError> {
+        self.send_limited(request, None).await
+    }
 
-            let response = send_request_reqwest::<CachingImpl>(client, request).await;
-            let response = parse_response(response).await;
+    async fn send_limited(
+        &self,
+        request: Request,
+        max_size: Option<usize>,
+    ) -> Result<Response, RequestError> {
+        let cache = self.cache.get(&request.url).unwrap();
+        if let Some(response) = cache.and_then(|data| transport::decode(&data).ok()) {
+            return response;
+        }
 
-            let json = serde_json::to_string(&response).unwrap();
-            let _ = caller.data().cache.put(&key, &json.as_bytes());
-            json
-        };
+        let key = request.url.clone();
+        let response = self.client.send_limited(request, max_size).await;
 
-        write_str(&mut caller, &memory, json.as_str()).await
-    })
+        if let Ok(bytes) = transport::encode(&response) {
+            let _ = self.cache.put(&key, &bytes);
+        }
+        response
+    }
 }
 
 pub struct Cache {
@@ -3,10 +3,18 @@ mod cache;
 
 use std::path::PathBuf;
 
-use cache::{Cache, CachingImpl};
+use cache::{Cache, CachingClient};
 use clap::{Parser, Subcommand};
-use quelle_core::prelude::{ExtensionConfig, Request};
-use quelle_engine::Runtime;
+use quelle_core::{
+    prelude::{ExtensionConfig, Request},
+    transport,
+};
+use quelle_engine::{
+    cache::ModuleCache,
+    client::{HttpClient, HttpConfig},
+    replay::Fixtures,
+    Runtime,
+};
 use simplelog::{Config, LevelFilter, TermLogger};
 use url::Url;
 
@@ -17,6 +25,10 @@ struct Cli {
     #[clap(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
 
+    /// Keep the compiled extensions in the user cache directory
+    #[clap(long)]
+    module_cache: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -54,6 +66,14 @@ enum Commands {
         /// Page used in search and popular
         #[arg(short, long, default_value = "1")]
         page: i32,
+
+        /// Record every request and its response into the directory
+        #[arg(long, conflicts_with = "replay")]
+        record: Option<PathBuf>,
+
+        /// Answer every request from the responses recorded into the directory
+        #[arg(long)]
+        replay: Option<PathBuf>,
     },
 
     /// Build the extensions into wasm
@@ -69,6 +89,10 @@ enum Commands {
         /// Build the extension(s) with release profile
         #[arg(short, long)]
         release: bool,
+
+        /// Build the extension(s) as components of the WIT world
+        #[arg(short, long)]
+        component: bool,
     },
 
     /// Read the compiled wasm files and create a record
@@ -120,6 +144,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .unwrap();
 
+    let module_cache = cli.module_cache.then(ModuleCache::user).flatten();
+
     match cli.command {
         Commands::Run {
             path,
@@ -130,21 +156,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             search,
             options,
             page,
+            record,
+            replay,
         } => {
             let config = ExtensionConfig {
                 level_filter: level,
             };
 
-            let mut runner = Runtime::builder()
-                .send_request(cache::send_request)
-                .build(&path, CachingImpl::new())
-                .await?;
+            let mut builder =
+                Runtime::builder().http_client(CachingClient::new(&HttpConfig::default())?);
+            if let Some(cache) = module_cache {
+                builder = builder.module_cache(cache);
+            }
+            let builder = match (record, replay) {
+                (Some(dir), _) => builder.fixtures(Fixtures::record(dir)),
+                (_, Some(dir)) => builder.fixtures(Fixtures::replay(dir)),
+                _ => builder,
+            };
+
+            let mut runner = builder.build_any(&path, ()).await?;
 
             runner.setup(&config).await?;
 
+            // Reading the meta allows the extension to reach its hosts
+            let source = runner.meta().await?;
             if meta {
-                let meta = runner.meta().await?;
-                println!("{meta:#?}");
+                println!("{source:#?}");
             }
 
             if let Some(url) = novel {
@@ -209,29 +246,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             extension,
             out,
             release,
+            component,
         } => {
-            build::build(extension, out, release)?;
+            build::build(extension, out, release, component)?;
         }
         Commands::Lock { dir } => {
-            quelle_lock::Lock::generate(&dir).await?;
+            quelle_lock::Lock::generate(&dir, module_cache.as_ref()).await?;
         }
         Commands::Cache { url, clear } => {
             if let Some(url) = url {
-                let data = CachingImpl::new();
+                let caching = CachingClient::new(&HttpConfig::default())?;
 
                 let key = url.clone();
-                let client = &data.client;
-
                 let request = Request::new(quelle_core::prelude::Method::Get, url);
+                let response = caching.client.send(request).await;
 
-                use quelle_engine::module::http::{parse_response, send_request_reqwest};
-                let response = send_request_reqwest::<CachingImpl>(client, request).await;
-                let response = parse_response(response).await;
-
-                let json = serde_json::to_string(&response).unwrap();
-                data.cache.put(&key, &json.as_bytes())?;
-                println!("{json}");
-                println!("{:?}", response.unwrap().text()?);
+                caching.cache.put(&key, &transport::encode(&response)?)?;
+                println!("{:?}", response.unwrap().decoded_text()?);
             }
 
             if clear {
@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use quelle_core::{
+    prelude::{HeaderMap, RequestError, Response},
+    transport,
+};
+
+fn response(size: usize) -> Result<Response, RequestError> {
+    let mut headers = HeaderMap::new();
+    headers.append("Content-Type", "text/html; charset=utf-8");
+    headers.append("Set-Cookie", "session=abc; Path=/");
+
+    let body = "<li><a href=\"/novel/chapter-1\">Chapter 1</a></li>"
+        .bytes()
+        .cycle()
+        .take(size)
+        .collect();
+
+    Ok(Response {
+        status: 200,
+        url: String::from("https://example.com/novel/chapters/page-1"),
+        body: Some(body),
+        headers,
+    })
+}
+
+fn roundtrip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("response roundtrip");
+
+    for size in [16 * 1024, 256 * 1024, 2 * 1024 * 1024] {
+        let value = response(size);
+
+        group.bench_with_input(BenchmarkId::new("json", size), &value, |b, value| {
+            b.iter(|| {
+                let bytes = serde_json::to_vec(black_box(value)).unwrap();
+                serde_json::from_slice::<Result<Response, RequestError>>(&bytes).unwrap()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("postcard", size), &value, |b, value| {
+            b.iter(|| {
+                let bytes = transport::encode(black_box(value)).unwrap();
+                transport::decode::<Result<Response, RequestError>>(&bytes).unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, roundtrip);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+/// The version of the interface between the engine and the extensions
+///
+/// Bumped whenever an export or import changes in a way that extensions
+/// built against an older version can not follow.
+///
+/// - 1: the `abi_version` export and postcard encoded requests and responses
+/// - 2: the `timeout` of [Request](crate::prelude::Request) and
+///   [QuelleError::DecodeFailed](crate::prelude::QuelleError::DecodeFailed)
+pub const ABI_VERSION: u32 = 2;
@@ -0,0 +1,172 @@
+//! Decoding of response bodies that are not necessarily UTF-8.
+//!
+//! The encoding is taken from the first of a byte order mark, the charset of the
+//! `Content-Type` header and a `<meta>` tag near the start of the document. Without
+//! any of them, the body is read as UTF-8 if it is valid and guessed otherwise.
+
+use std::borrow::Cow;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+use url::Url;
+
+use crate::error::QuelleError;
+
+/// The number of bytes searched for a `<meta>` tag declaring the charset
+const META_PRESCAN_LEN: usize = 1024;
+
+pub(crate) fn decode<'a>(
+    body: &'a [u8],
+    content_type: Option<&str>,
+    url: &str,
+) -> Result<Cow<'a, str>, QuelleError> {
+    let (encoding, body) = match Encoding::for_bom(body) {
+        Some((encoding, bom_len)) => (encoding, &body[bom_len..]),
+        None => {
+            let declared = content_type
+                .and_then(from_content_type)
+                .or_else(|| from_meta(body));
+            (declared.unwrap_or_else(|| sniff(body, url)), body)
+        }
+    };
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(body)
+        .ok_or_else(|| {
+            QuelleError::DecodeFailed(format!("the body is not valid {}", encoding.name()))
+        })
+}
+
+/// The encoding named by the `charset` parameter of a `Content-Type` value
+fn from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(
+            value
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .as_bytes(),
+        )
+    })
+}
+
+/// The encoding declared by a `<meta charset>` or `<meta http-equiv>` tag
+fn from_meta(body: &[u8]) -> Option<&'static Encoding> {
+    let head = &body[..body.len().min(META_PRESCAN_LEN)];
+    let head = head.to_ascii_lowercase();
+
+    let mut rest = head.as_slice();
+    while let Some(start) = find(rest, b"<meta") {
+        let tag = &rest[start..];
+        let tag = &tag[..find(tag, b">").unwrap_or(tag.len())];
+
+        if let Some(encoding) = charset_of_tag(tag) {
+            // Pages in an UTF-16 encoding can not declare it in ascii
+            return Some(match encoding {
+                e if e == UTF_16BE || e == UTF_16LE => UTF_8,
+                e if e == X_USER_DEFINED => WINDOWS_1252,
+                e => e,
+            });
+        }
+        rest = &rest[start + tag.len()..];
+    }
+
+    None
+}
+
+fn charset_of_tag(tag: &[u8]) -> Option<&'static Encoding> {
+    let start = find(tag, b"charset")? + "charset".len();
+    let value = tag[start..].trim_ascii_start().strip_prefix(b"=")?;
+    let value = value.trim_ascii_start();
+    let value = value
+        .strip_prefix(b"\"")
+        .or_else(|| value.strip_prefix(b"'"))
+        .unwrap_or(value);
+
+    let end = value
+        .iter()
+        .position(|c| matches!(c, b'"' | b'\'' | b';' | b'/') || c.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    Encoding::for_label(&value[..end])
+}
+
+/// UTF-8 if the body is valid, a guess from the bytes and top-level domain otherwise
+fn sniff(body: &[u8], url: &str) -> &'static Encoding {
+    if std::str::from_utf8(body).is_ok() {
+        return UTF_8;
+    }
+
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string));
+    let tld = host
+        .as_deref()
+        .and_then(|host| host.rsplit('.').next())
+        .map(str::as_bytes);
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(body, true);
+    detector.guess(tld, true)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{EUC_KR, GBK, SHIFT_JIS};
+
+    use super::*;
+
+    const URL: &str = "https://example.com/novel";
+
+    #[test]
+    fn should_follow_content_type() {
+        let (body, _, _) = SHIFT_JIS.encode("異世界の物語");
+        let text = decode(&body, Some("text/html; charset=Shift_JIS"), URL).unwrap();
+        assert_eq!(text, "異世界の物語");
+
+        let (body, _, _) = EUC_KR.encode("소설");
+        let text = decode(&body, Some(r#"text/html;charset="euc-kr""#), URL).unwrap();
+        assert_eq!(text, "소설");
+    }
+
+    #[test]
+    fn should_follow_meta_tags() {
+        let (title, _, _) = GBK.encode("小说");
+        let page = |head: &str| [head.as_bytes(), &title].concat();
+
+        let body = page(r#"<html><head><meta charset="gbk"><title>"#);
+        assert!(decode(&body, Some("text/html"), URL)
+            .unwrap()
+            .ends_with("小说"));
+
+        let body =
+            page(r#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=GB2312"><title>"#);
+        assert!(decode(&body, None, URL).unwrap().ends_with("小说"));
+    }
+
+    #[test]
+    fn should_prefer_bom_and_sniff_without_declaration() {
+        let text = decode(b"\xEF\xBB\xBFplain", Some("text/html; charset=gbk"), URL).unwrap();
+        assert_eq!(text, "plain");
+
+        assert_eq!(decode("日本語".as_bytes(), None, URL).unwrap(), "日本語");
+
+        let (body, _, _) = SHIFT_JIS.encode("これは日本語の小説のタイトルです。第一章");
+        let text = decode(&body, None, "https://example.jp/novel").unwrap();
+        assert_eq!(text, "これは日本語の小説のタイトルです。第一章");
+    }
+
+    #[test]
+    fn should_fail_on_invalid_bytes() {
+        let result = decode(b"caf\xC3", Some("text/html; charset=utf-8"), URL);
+        assert!(matches!(result, Err(QuelleError::DecodeFailed(_))));
+    }
+}
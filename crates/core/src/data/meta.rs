@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::{Attribute, ReadingDirection};
-use crate::error::ParseError;
+use crate::{error::ParseError, http::RateLimit};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Meta {
@@ -13,6 +13,14 @@ pub struct Meta {
     pub base_urls: Vec<String>,
     pub rds: Vec<ReadingDirection>,
     pub attrs: Vec<Attribute>,
+
+    /// The request rate the source website tolerates
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+
+    /// Hosts other than the base urls the extension sends requests to
+    #[serde(default)]
+    pub extra_hosts: Vec<String>,
 }
 
 impl Meta {
@@ -49,6 +57,49 @@ impl Meta {
     pub fn home_url(&self) -> &str {
         &self.base_urls[0]
     }
+
+    /// The base url the url belongs to, if any
+    pub fn base_url_of(&self, url: &str) -> Option<&str> {
+        self.base_urls
+            .iter()
+            .map(String::as_str)
+            .find(|base_url| strip_base_url(url, base_url).is_some())
+    }
+
+    /// Move the url from the base url it belongs to onto another base url
+    ///
+    /// The mirrors listed in [Meta::base_urls] serve the same paths, so the rest
+    /// of the url is kept as it is. An `https` url is never moved onto an `http`
+    /// base url.
+    pub fn rebase_url(&self, url: &str, base_url: &str) -> Option<String> {
+        if is_downgrade(url, base_url) {
+            return None;
+        }
+        let rest = strip_base_url(url, self.base_url_of(url)?)?;
+        Some(format!("{}{rest}", base_url.trim_end_matches('/')))
+    }
+}
+
+/// The rest of the url following the base url, if the url starts with it
+///
+/// Only whole hosts and path segments match, so `https://example.com` is not
+/// the base url of `https://example.com.cn`.
+pub fn strip_base_url<'a>(url: &'a str, base_url: &str) -> Option<&'a str> {
+    let rest = url.strip_prefix(base_url.trim_end_matches('/'))?;
+    if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// Whether moving the url onto the base url would drop `https` for plain `http`
+pub fn is_downgrade(url: &str, base_url: &str) -> bool {
+    let has_scheme = |url: &str, scheme: &str| {
+        url.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    };
+    has_scheme(url, "https://") && has_scheme(base_url, "http://")
 }
 
 fn base_url(url: Url) -> String {
@@ -157,6 +208,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_rebase_onto_mirror() {
+        let meta = Meta {
+            base_urls: vec![
+                String::from("https://novelfull.com"),
+                String::from("https://novelfull.net/"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            meta.base_url_of("https://novelfull.net/novel.html"),
+            Some("https://novelfull.net/")
+        );
+        assert_eq!(
+            meta.base_url_of("https://novelfull.com.cn/novel.html"),
+            None
+        );
+        assert_eq!(
+            meta.rebase_url(
+                "https://novelfull.com/novel.html?page=2",
+                "https://novelfull.net/"
+            )
+            .as_deref(),
+            Some("https://novelfull.net/novel.html?page=2")
+        );
+        assert_eq!(
+            meta.rebase_url("https://novelfull.net", "https://novelfull.com")
+                .as_deref(),
+            Some("https://novelfull.com")
+        );
+        assert_eq!(
+            meta.rebase_url("https://example.com/a", "https://novelfull.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn should_not_rebase_onto_plain_http() {
+        let meta = Meta {
+            base_urls: vec![
+                String::from("https://novelfull.com"),
+                String::from("http://novelfull.com"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            meta.rebase_url("https://novelfull.com/novel.html", "http://novelfull.com"),
+            None
+        );
+        assert_eq!(
+            meta.rebase_url("http://novelfull.com/novel.html", "https://novelfull.com")
+                .as_deref(),
+            Some("https://novelfull.com/novel.html")
+        );
+    }
+
     #[test]
     fn should_get_base_url() {
         assert_eq!(
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 pub use chapter::{Chapter, Content, TaggedDateTime};
-pub use meta::Meta;
+pub use meta::{is_downgrade, strip_base_url, Meta};
 pub use novel::{BasicNovel, Novel};
 
 #[derive(Serialize, Deserialize, Debug)]
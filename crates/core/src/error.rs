@@ -20,6 +20,9 @@ pub enum QuelleError {
 
     #[error("{0}")]
     WasmAbiError(String),
+
+    #[error("failed to decode the response: {0}")]
+    DecodeFailed(String),
 }
 
 #[derive(Serialize, Deserialize, thiserror::Error, Debug)]
@@ -33,6 +36,9 @@ pub enum ParseError {
     #[error("failed to parse url")]
     FailedURLParse,
 
+    #[error("failed to parse json")]
+    FailedJsonParse,
+
     #[error("failed to parse int from str")]
     ParseIntError,
 
@@ -40,6 +46,22 @@ pub enum ParseError {
     Other(String),
 }
 
+/// A failed call to the key-value storage of the extension
+#[derive(Serialize, Deserialize, thiserror::Error, Debug)]
+pub enum StorageError {
+    #[error("storage is unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("failed to save the storage: {0}")]
+    Io(String),
+
+    #[error("failed to pass the storage call between the extension and the engine")]
+    Serial,
+}
+
 impl ParseError {
     pub fn other<S: Into<String>>(s: S) -> Self {
         Self::Other(s.into())
@@ -1,20 +1,42 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, time::Duration};
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::{
+    charset,
+    error::{ParseError, QuelleError},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Request {
     pub method: Method,
     pub url: String,
-    pub params: Option<String>,
+    pub params: Option<Vec<(String, String)>>,
     pub data: Option<Body>,
-    pub headers: Option<String>,
+    pub headers: Option<Vec<(String, String)>>,
+    /// The time in milliseconds the request may take, in place of the one of the engine
+    #[serde(default)]
+    pub timeout: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Body {
+    /// Sent as `multipart/form-data`
     Form(HashMap<String, String>),
+
+    /// Sent as `application/x-www-form-urlencoded`
+    UrlEncoded(HashMap<String, String>),
+
+    /// A serialized json document sent as `application/json`
+    Json(String),
+
+    /// Raw bytes sent with the given content type
+    Bytes {
+        content_type: String,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    },
 }
 
 impl Request {
@@ -25,6 +47,7 @@ impl Request {
             params: None,
             data: None,
             headers: None,
+            timeout: None,
         }
     }
 
@@ -38,26 +61,126 @@ impl Request {
         Request::new(Method::Post, url)
     }
 
+    /// Append a query parameter to the request url
+    pub fn param<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.params
+            .get_or_insert_with(Vec::new)
+            .push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a header to the request
+    ///
+    /// Adding the same header more than once sends all of the values.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers
+            .get_or_insert_with(Vec::new)
+            .push((key.into(), value.into()));
+        self
+    }
+
+    /// Give up on the request after the timeout instead of the one of the engine
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Send the body as a multipart form
     #[inline]
     pub fn form(mut self, value: HashMap<String, String>) -> Self {
         self.data = Some(Body::Form(value));
         self
     }
 
+    /// Send the body as an url-encoded form
+    #[inline]
+    pub fn url_encoded(mut self, value: HashMap<String, String>) -> Self {
+        self.data = Some(Body::UrlEncoded(value));
+        self
+    }
+
+    /// Serialize the value and send it as a json body
+    pub fn json<T: Serialize>(mut self, value: &T) -> Result<Self, serde_json::Error> {
+        let json = serde_json::to_string(value)?;
+        self.data = Some(Body::Json(json));
+        Ok(self)
+    }
+
+    /// Send raw bytes with the given content type
+    pub fn bytes<S: Into<String>>(mut self, content_type: S, data: Vec<u8>) -> Self {
+        self.data = Some(Body::Bytes {
+            content_type: content_type.into(),
+            data,
+        });
+        self
+    }
+
+    /// Append the fields of a json object as query parameters
+    ///
+    /// See [`json_pairs`] for how the values are turned into strings.
     pub fn json_params(mut self, value: &Value) -> Result<Self, serde_json::Error> {
-        let params = serde_json::to_string(value)?;
-        self.params = Some(params);
+        let params = json_pairs(value)?;
+        self.params.get_or_insert_with(Vec::new).extend(params);
         Ok(self)
     }
 
+    /// Add the fields of a json object as headers
+    ///
+    /// See [`json_pairs`] for how the values are turned into strings.
     pub fn json_headers(mut self, value: &Value) -> Result<Self, serde_json::Error> {
-        let headers = serde_json::to_string(value)?;
-        self.headers = Some(headers);
+        let headers = json_pairs(value)?;
+        self.headers.get_or_insert_with(Vec::new).extend(headers);
         Ok(self)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Flatten a json object into key-value pairs in the order of its fields
+///
+/// Strings are taken as they are, numbers and booleans are formatted and null
+/// fields are skipped. An array repeats the key once for each of its items.
+fn json_pairs(value: &Value) -> Result<Vec<(String, String)>, serde_json::Error> {
+    fn scalar(key: &str, value: &Value) -> Result<Option<String>, serde_json::Error> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(s) => Ok(Some(s.clone())),
+            Value::Number(n) => Ok(Some(n.to_string())),
+            Value::Bool(b) => Ok(Some(b.to_string())),
+            Value::Array(_) | Value::Object(_) => Err(serde::de::Error::custom(format!(
+                "expected a string, number or boolean for '{key}'"
+            ))),
+        }
+    }
+
+    let Value::Object(map) = value else {
+        return Err(serde::de::Error::custom("expected a json object"));
+    };
+
+    let mut pairs = Vec::with_capacity(map.len());
+    for (key, value) in map {
+        let values = match value {
+            Value::Array(items) => items.iter().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+
+        for value in values {
+            if let Some(value) = scalar(key, value)? {
+                pairs.push((key.clone(), value));
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Method {
     Get,
     Post,
@@ -69,8 +192,11 @@ pub enum Method {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Response {
     pub status: usize,
+    /// The final url of the response after following redirects
+    pub url: String,
+    #[serde(with = "serde_bytes")]
     pub body: Option<Vec<u8>>,
-    pub headers: Option<String>,
+    pub headers: HeaderMap,
 }
 
 impl Response {
@@ -80,6 +206,118 @@ impl Response {
             .map(|body| std::str::from_utf8(body))
             .transpose()
     }
+
+    /// Decode the body in the charset of the `Content-Type` header or `<meta>` tag
+    ///
+    /// Without a declared charset, the body is decoded as UTF-8 when valid and in a
+    /// guessed encoding otherwise. Bytes that are invalid in the encoding fail with
+    /// [QuelleError::DecodeFailed], as does a missing body.
+    pub fn decoded_text(&self) -> Result<Cow<'_, str>, QuelleError> {
+        let body = self.body.as_deref().ok_or_else(|| {
+            QuelleError::DecodeFailed(format!("the response of '{}' has no body", self.url))
+        })?;
+        charset::decode(body, self.header("content-type"), &self.url)
+    }
+
+    /// Deserialize the body as json
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ParseError> {
+        let body = self.body.as_deref().unwrap_or_default();
+        serde_json::from_slice(body).map_err(|_| ParseError::FailedJsonParse)
+    }
+
+    /// The first value of the header with the given name
+    #[inline]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// The values of every `Set-Cookie` header in the response
+    #[inline]
+    pub fn cookies(&self) -> &[String] {
+        self.headers.get_all("set-cookie")
+    }
+
+    /// Whether the status is within 200-299
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Response headers with case-insensitive names
+///
+/// Repeated headers keep all of their values in the order they were received.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct HeaderMap(HashMap<String, Vec<String>>);
+
+impl HeaderMap {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a value to the header without removing the existing values
+    pub fn append<K, V>(&mut self, name: K, value: V)
+    where
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        self.0
+            .entry(name.as_ref().to_ascii_lowercase())
+            .or_default()
+            .push(value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).first().map(String::as_str)
+    }
+
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.0
+            .get(&name.to_ascii_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(&name.to_ascii_lowercase())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .flat_map(|(name, values)| values.iter().map(|value| (name.as_str(), value.as_str())))
+    }
+}
+
+/// Politeness limits for the requests sent to a single site
+///
+/// Times are in milliseconds. Fields left as `None` are not limited.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The minimum time between the start of two requests
+    pub interval: Option<u64>,
+
+    /// The maximum number of requests in flight at once
+    pub concurrency: Option<usize>,
+
+    /// The upper bound of a random delay added before each request
+    pub jitter: Option<u64>,
+}
+
+impl RateLimit {
+    /// Combine two limits, keeping the stricter value of each field
+    pub fn stricter(&self, other: &RateLimit) -> RateLimit {
+        RateLimit {
+            interval: self.interval.max(other.interval),
+            concurrency: match (self.concurrency, other.concurrency) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            jitter: self.jitter.max(other.jitter),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, thiserror::Error, Debug)]
@@ -114,6 +352,12 @@ pub enum RequestErrorKind {
     Body,
     Timeout,
     Unknown,
+    /// The connection to the server could not be established
+    Connect,
+    /// The request was refused by the network rules of the extension
+    Blocked,
+    /// The response body was larger than the extension is allowed to receive
+    TooLarge,
 }
 
 impl From<RequestError> for BoxedRequestError {
@@ -122,6 +366,12 @@ impl From<RequestError> for BoxedRequestError {
     }
 }
 
+impl From<BoxedRequestError> for RequestError {
+    fn from(value: BoxedRequestError) -> Self {
+        *value.0
+    }
+}
+
 #[cfg(feature = "reqwest")]
 impl From<Method> for reqwest::Method {
     fn from(value: Method) -> Self {
@@ -147,7 +397,9 @@ impl From<reqwest::Error> for RequestError {
             RequestErrorKind::Body
         } else if error.is_redirect() {
             RequestErrorKind::Redirect
-        } else if error.is_request() {
+        } else if error.is_connect() {
+            RequestErrorKind::Connect
+        } else if error.is_request() || error.is_builder() {
             RequestErrorKind::Request
         } else if error.is_status() {
             RequestErrorKind::Status(error.status().unwrap_or_default().as_u16())
@@ -158,3 +410,130 @@ impl From<reqwest::Error> for RequestError {
         RequestError { kind, url, message }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn should_append_params_and_headers() {
+        let request = Request::get(String::from("https://example.com"))
+            .param("page", "1")
+            .param("tag", "a")
+            .param("tag", "b")
+            .header("Referer", "https://example.com");
+
+        assert_eq!(
+            request.params,
+            Some(vec![
+                (String::from("page"), String::from("1")),
+                (String::from("tag"), String::from("a")),
+                (String::from("tag"), String::from("b")),
+            ])
+        );
+        assert_eq!(
+            request.headers,
+            Some(vec![(
+                String::from("Referer"),
+                String::from("https://example.com")
+            )])
+        );
+    }
+
+    #[test]
+    fn should_set_json_body() {
+        let request = Request::post(String::from("https://example.com"))
+            .json(&json!({ "query": "title" }))
+            .unwrap();
+
+        match request.data {
+            Some(Body::Json(data)) => assert_eq!(data, r#"{"query":"title"}"#),
+            other => panic!("expected json body, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn should_keep_repeated_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("set-cookie", "b=2");
+        headers.append("Content-Type", "text/html");
+
+        let response = Response {
+            status: 200,
+            url: String::from("https://example.com"),
+            body: None,
+            headers,
+        };
+
+        assert_eq!(response.header("content-type"), Some("text/html"));
+        assert_eq!(response.header("SET-COOKIE"), Some("a=1"));
+        assert_eq!(response.cookies(), &["a=1", "b=2"]);
+        assert!(response.is_success());
+    }
+
+    #[test]
+    fn should_parse_json_body() {
+        let response = Response {
+            status: 200,
+            url: String::from("https://example.com"),
+            body: Some(br#"{"page":2}"#.to_vec()),
+            headers: HeaderMap::new(),
+        };
+
+        let value = response.json::<HashMap<String, i32>>().unwrap();
+        assert_eq!(value.get("page"), Some(&2));
+    }
+
+    #[test]
+    fn should_stringify_json_params_in_order() {
+        let request = Request::get(String::from("https://example.com"))
+            .json_params(&json!({
+                "page": 1,
+                "query": "title",
+                "tag": ["a", "b"],
+                "completed": true,
+                "author": null,
+            }))
+            .unwrap();
+
+        assert_eq!(
+            request.params,
+            Some(vec![
+                (String::from("page"), String::from("1")),
+                (String::from("query"), String::from("title")),
+                (String::from("tag"), String::from("a")),
+                (String::from("tag"), String::from("b")),
+                (String::from("completed"), String::from("true")),
+            ])
+        );
+
+        let request = Request::get(String::from("https://example.com"));
+        assert!(request.clone().json_params(&json!(["page"])).is_err());
+        assert!(request.json_headers(&json!({ "a": { "b": 1 } })).is_err());
+    }
+
+    #[test]
+    fn should_keep_stricter_rate_limit() {
+        let user = RateLimit {
+            interval: Some(1000),
+            concurrency: None,
+            jitter: Some(100),
+        };
+        let hint = RateLimit {
+            interval: Some(500),
+            concurrency: Some(2),
+            jitter: None,
+        };
+
+        let expected = RateLimit {
+            interval: Some(1000),
+            concurrency: Some(2),
+            jitter: Some(100),
+        };
+        assert_eq!(user.stricter(&hint), expected);
+        assert_eq!(hint.stricter(&user), expected);
+    }
+}
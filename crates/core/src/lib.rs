@@ -1,3 +1,5 @@
+pub mod abi;
+mod charset;
 pub mod config;
 pub mod data;
 pub mod error;
@@ -5,3 +7,5 @@ pub mod filter;
 mod http;
 pub mod log;
 pub mod prelude;
+pub mod time;
+pub mod transport;
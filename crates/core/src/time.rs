@@ -0,0 +1,99 @@
+use chrono::{DateTime, Duration, Months, Utc};
+
+use crate::data::TaggedDateTime;
+
+/// Parse a date relative to `now`, like `3 hours ago`, `an hour ago` or `yesterday`
+///
+/// Units may be spelled out, abbreviated or plural (`5 mins ago`, `2d ago`).
+/// Returns [None] for text that is not a relative date.
+pub fn parse_relative(text: &str, now: DateTime<Utc>) -> Option<TaggedDateTime> {
+    let text = text.trim().to_ascii_lowercase();
+
+    let date = match text.as_str() {
+        "now" | "just now" | "today" | "a moment ago" | "moments ago" => now,
+        "yesterday" => now.checked_sub_signed(Duration::days(1))?,
+        _ => {
+            let text = text.strip_suffix("ago")?.trim_end();
+            let digits = text
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(text.len());
+
+            let (amount, unit) = match text.split_at(digits) {
+                ("", text) => match text.split_once(' ')? {
+                    ("a" | "an" | "one", unit) => (1, unit),
+                    _ => return None,
+                },
+                (amount, unit) => (amount.parse().ok()?, unit),
+            };
+
+            subtract(now, amount, unit.trim())?
+        }
+    };
+
+    Some(TaggedDateTime::Utc(date))
+}
+
+fn subtract(now: DateTime<Utc>, amount: u32, unit: &str) -> Option<DateTime<Utc>> {
+    let unit = match unit {
+        "s" => unit,
+        _ => unit.strip_suffix('s').unwrap_or(unit),
+    };
+
+    let amount = i64::from(amount);
+    match unit {
+        "second" | "sec" | "s" => now.checked_sub_signed(Duration::seconds(amount)),
+        "minute" | "min" | "m" => now.checked_sub_signed(Duration::minutes(amount)),
+        "hour" | "hr" | "h" => now.checked_sub_signed(Duration::hours(amount)),
+        "day" | "d" => now.checked_sub_signed(Duration::days(amount)),
+        "week" | "wk" | "w" => now.checked_sub_signed(Duration::weeks(amount)),
+        "month" | "mo" => now.checked_sub_months(Months::new(u32::try_from(amount).ok()?)),
+        "year" | "yr" | "y" => {
+            now.checked_sub_months(Months::new(u32::try_from(amount * 12).ok()?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap()
+    }
+
+    fn parse(text: &str) -> Option<String> {
+        match parse_relative(text, now())? {
+            TaggedDateTime::Utc(date) => Some(date.format("%Y-%m-%d %H:%M:%S").to_string()),
+            TaggedDateTime::Local(date) => Some(date.to_string()),
+        }
+    }
+
+    #[test]
+    fn should_parse_relative_dates() {
+        assert_eq!(parse("just now").as_deref(), Some("2024-03-31 12:00:00"));
+        assert_eq!(parse("Yesterday").as_deref(), Some("2024-03-30 12:00:00"));
+        assert_eq!(parse("3 hours ago").as_deref(), Some("2024-03-31 09:00:00"));
+        assert_eq!(parse("an hour ago").as_deref(), Some("2024-03-31 11:00:00"));
+        assert_eq!(parse("45 mins ago").as_deref(), Some("2024-03-31 11:15:00"));
+        assert_eq!(parse("2d ago").as_deref(), Some("2024-03-29 12:00:00"));
+        assert_eq!(parse("1 week ago").as_deref(), Some("2024-03-24 12:00:00"));
+        assert_eq!(parse("1 month ago").as_deref(), Some("2024-02-29 12:00:00"));
+        assert_eq!(parse("2 years ago").as_deref(), Some("2022-03-31 12:00:00"));
+    }
+
+    #[test]
+    fn should_reject_other_text() {
+        for text in [
+            "",
+            "ago",
+            "Mar 3, 2024",
+            "3 fortnights ago",
+            "some hours ago",
+        ] {
+            assert_eq!(parse(text), None, "{text} was parsed");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! The binary format used to pass http requests and responses across the wasm boundary.
+//!
+//! Values are encoded with [postcard], so byte buffers such as [Response::body](crate::prelude::Response)
+//! are copied as raw bytes instead of being expanded into a json number array.
+
+use serde::{Deserialize, Serialize};
+
+pub use postcard::Error;
+
+#[inline]
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    postcard::to_allocvec(value)
+}
+
+#[inline]
+pub fn decode<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, Error> {
+    postcard::from_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{HeaderMap, Request, RequestError, RequestErrorKind, Response};
+
+    #[test]
+    fn should_roundtrip_request() {
+        let request = Request::post(String::from("https://example.com"))
+            .param("page", "2")
+            .header("Referer", "https://example.com")
+            .bytes("text/plain", b"hello".to_vec());
+
+        let bytes = encode(&request).unwrap();
+        let decoded = decode::<Request>(&bytes).unwrap();
+
+        assert_eq!(decoded.url, request.url);
+        assert_eq!(decoded.params, request.params);
+        assert_eq!(decoded.headers, request.headers);
+    }
+
+    #[test]
+    fn should_roundtrip_response_result() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/html");
+
+        let response: Result<Response, RequestError> = Ok(Response {
+            status: 200,
+            url: String::from("https://example.com"),
+            body: Some(vec![0, 1, 2, 255]),
+            headers,
+        });
+
+        let bytes = encode(&response).unwrap();
+        let decoded = decode::<Result<Response, RequestError>>(&bytes)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(decoded.body, Some(vec![0, 1, 2, 255]));
+        assert_eq!(decoded.header("content-type"), Some("text/html"));
+
+        let error: Result<Response, RequestError> = Err(RequestError {
+            kind: RequestErrorKind::Status(404),
+            url: None,
+            message: String::from("not found"),
+        });
+
+        let bytes = encode(&error).unwrap();
+        assert!(decode::<Result<Response, RequestError>>(&bytes)
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn should_copy_body_bytes_without_expansion() {
+        let response = Response {
+            status: 200,
+            url: String::new(),
+            body: Some(vec![b'a'; 4096]),
+            headers: HeaderMap::new(),
+        };
+
+        let bytes = encode(&response).unwrap();
+        assert!(bytes.len() < 4096 + 16);
+    }
+}
@@ -0,0 +1,187 @@
+//! Run extensions of either kind while they are migrated to the component model
+
+use std::path::Path;
+
+use log::LevelFilter;
+use quelle_core::prelude::{BasicNovel, Content, ExtensionConfig, FieldMap, Meta, Novel};
+use quelle_core::prelude::{Request, Response};
+
+use crate::{
+    cache::ModuleCache, capabilities::Capabilities, client::HttpConfig,
+    component::ComponentRuntime, cookie::CookieJar, data::DefaultImpl, error, Runtime,
+    RuntimeBuilder,
+};
+
+/// Call the method on the runtime of either kind
+macro_rules! dispatch {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            AnyRuntime::Module(runtime) => runtime.$method($($arg),*),
+            AnyRuntime::Component(runtime) => runtime.$method($($arg),*),
+        }
+    };
+    ($self:ident.$method:ident($($arg:expr),*).await) => {
+        match $self {
+            AnyRuntime::Module(runtime) => runtime.$method($($arg),*).await,
+            AnyRuntime::Component(runtime) => runtime.$method($($arg),*).await,
+        }
+    };
+}
+
+/// A runtime for an extension built against the legacy ABI or as a component
+///
+/// Built with [RuntimeBuilder::build_any], which tells the kinds apart with
+/// [is_component](crate::component::is_component).
+pub enum AnyRuntime<D> {
+    Module(Box<Runtime<D>>),
+    Component(Box<ComponentRuntime<D>>),
+}
+
+impl AnyRuntime<DefaultImpl> {
+    /// Build a runtime with the http config, see [Runtime::new]
+    ///
+    /// The module cache only applies to extensions built against the legacy ABI.
+    pub async fn new(
+        path: &Path,
+        http: &HttpConfig,
+        module_cache: Option<ModuleCache>,
+    ) -> error::Result<Self> {
+        let data = DefaultImpl::new(http)?;
+        let mut builder = RuntimeBuilder::default()
+            .http_client(data.client.clone())
+            .rate_limiter(http.rate_limiter());
+        if let Some(cache) = module_cache {
+            builder = builder.module_cache(cache);
+        }
+        builder.build_any(path, data).await
+    }
+}
+
+impl<D> AnyRuntime<D>
+where
+    D: Send + Sync,
+{
+    pub fn is_component(&self) -> bool {
+        matches!(self, AnyRuntime::Component(_))
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        dispatch!(self.capabilities())
+    }
+
+    pub fn cookie_jar(&self) -> &CookieJar {
+        dispatch!(self.cookie_jar())
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        dispatch!(self.is_poisoned())
+    }
+
+    pub async fn setup(&mut self, config: &ExtensionConfig) -> error::Result<()> {
+        dispatch!(self.setup(config).await)
+    }
+
+    pub async fn set_level_filter(&mut self, level_filter: LevelFilter) -> error::Result<()> {
+        dispatch!(self.set_level_filter(level_filter).await)
+    }
+
+    pub async fn meta(&mut self) -> error::Result<Meta> {
+        dispatch!(self.meta().await)
+    }
+
+    pub async fn fetch_novel(&mut self, url: &str) -> error::Result<Novel> {
+        dispatch!(self.fetch_novel(url).await)
+    }
+
+    pub async fn fetch_chapter_content(&mut self, url: &str) -> error::Result<Content> {
+        dispatch!(self.fetch_chapter_content(url).await)
+    }
+
+    pub fn popular_supported(&self) -> bool {
+        dispatch!(self.popular_supported())
+    }
+
+    pub async fn popular_url(&mut self, page: i32) -> error::Result<String> {
+        dispatch!(self.popular_url(page).await)
+    }
+
+    pub async fn popular(&mut self, page: i32) -> error::Result<Vec<BasicNovel>> {
+        dispatch!(self.popular(page).await)
+    }
+
+    pub fn text_search_supported(&self) -> bool {
+        dispatch!(self.text_search_supported())
+    }
+
+    pub async fn text_search_url(&mut self, query: &str, page: i32) -> error::Result<String> {
+        dispatch!(self.text_search_url(query, page).await)
+    }
+
+    pub async fn text_search(&mut self, query: &str, page: i32) -> error::Result<Vec<BasicNovel>> {
+        dispatch!(self.text_search(query, page).await)
+    }
+
+    pub fn filter_search_supported(&self) -> bool {
+        dispatch!(self.filter_search_supported())
+    }
+
+    pub async fn filter_options(&mut self) -> error::Result<FieldMap> {
+        dispatch!(self.filter_options().await)
+    }
+
+    pub async fn filter_search_url(&mut self, params: &str, page: i32) -> error::Result<String> {
+        dispatch!(self.filter_search_url(params, page).await)
+    }
+
+    pub async fn filter_search(
+        &mut self,
+        params: &str,
+        page: i32,
+    ) -> error::Result<Vec<BasicNovel>> {
+        dispatch!(self.filter_search(params, page).await)
+    }
+
+    pub fn image_request_supported(&self) -> bool {
+        dispatch!(self.image_request_supported())
+    }
+
+    pub async fn image_request(&mut self, url: &str) -> error::Result<Request> {
+        dispatch!(self.image_request(url).await)
+    }
+
+    pub async fn fetch_image(&mut self, url: &str) -> error::Result<Response> {
+        dispatch!(self.fetch_image(url).await)
+    }
+}
+
+impl<D> From<Runtime<D>> for AnyRuntime<D> {
+    fn from(runtime: Runtime<D>) -> Self {
+        AnyRuntime::Module(Box::new(runtime))
+    }
+}
+
+impl<D> From<ComponentRuntime<D>> for AnyRuntime<D> {
+    fn from(runtime: ComponentRuntime<D>) -> Self {
+        AnyRuntime::Component(Box::new(runtime))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, path::PathBuf};
+
+    use super::*;
+    use crate::{component::SOURCE, error::Error};
+
+    #[tokio::test]
+    async fn should_build_either_kind() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trap.wat");
+        let runtime = Runtime::builder().build_any(&path, ()).await.unwrap();
+        assert!(!runtime.is_component());
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"(component)").unwrap();
+        let runtime = Runtime::builder().build_any(file.path(), ()).await;
+        assert!(matches!(runtime, Err(Error::MissingExport(name)) if name == SOURCE));
+    }
+}
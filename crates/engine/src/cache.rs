@@ -0,0 +1,287 @@
+use std::{
+    fs::{self, DirBuilder, File},
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use wasmtime::{Engine, Module};
+
+use crate::error;
+
+const MODULE_EXTENSION: &str = "cwasm";
+const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// The size the cache in the user cache directory may grow to
+const DEFAULT_MAX_SIZE: u64 = 256 * 1024 * 1024;
+
+/// An on-disk cache of compiled modules
+///
+/// Entries are keyed by the wasm content, the wasmtime version and the engine
+/// config, so a module is only compiled again when one of them changes. Once
+/// the cache grows past `max_size` bytes the least recently used entries are
+/// removed.
+///
+/// Cached modules are loaded as native code, so the directory must only be
+/// writable by the user running the engine. It is created private to the user
+/// on unix.
+#[derive(Debug, Clone)]
+pub struct ModuleCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl ModuleCache {
+    pub fn new(dir: PathBuf, max_size: u64) -> Self {
+        Self { dir, max_size }
+    }
+
+    /// A cache in the cache directory of the user, if the platform has one
+    pub fn user() -> Option<Self> {
+        let dir = dirs::cache_dir()?.join("quelle").join("modules");
+        Some(Self::new(dir, DEFAULT_MAX_SIZE))
+    }
+
+    /// Load the compiled module from the cache, compiling and storing it on a miss
+    pub fn load(&self, engine: &Engine, path: &Path) -> error::Result<Module> {
+        let wasm = fs::read(path)?;
+        let key = Self::key(engine, &wasm);
+
+        if let Some(module) = self.get(engine, &key) {
+            debug!("loaded compiled module for '{}' from cache", path.display());
+            return Ok(module);
+        }
+
+        let module = Module::new(engine, &wasm)?;
+        if let Err(e) = self.put(&key, &module) {
+            warn!("failed to cache compiled module: {e}");
+        }
+
+        Ok(module)
+    }
+
+    fn key(engine: &Engine, wasm: &[u8]) -> String {
+        let mut digest = DigestHasher(Sha256::new());
+        digest.0.update(wasm);
+        // Covers the wasmtime version and every setting that affects compilation
+        engine.precompile_compatibility_hash().hash(&mut digest);
+        format!("{:x}", digest.0.finalize())
+    }
+
+    fn get(&self, engine: &Engine, key: &str) -> Option<Module> {
+        let path = self.entry_path(key, MODULE_EXTENSION);
+        let bytes = fs::read(&path).ok()?;
+        let checksum = fs::read_to_string(self.entry_path(key, CHECKSUM_EXTENSION)).ok();
+
+        if checksum.as_deref() != Some(&format!("{:x}", Sha256::digest(&bytes))) {
+            warn!("rejected corrupted cache entry '{}'", path.display());
+            self.remove(key);
+            return None;
+        }
+
+        // SAFETY: the checksum only catches partial writes and corruption, as it is
+        // stored in the same directory and anyone able to write the entry can write
+        // it too. Loading the entry trusts the directory, which is why it is kept
+        // private to the user. Wasmtime rejects artifacts from another version or
+        // engine config.
+        match unsafe { Module::deserialize(engine, &bytes) } {
+            Ok(module) => {
+                let _ = File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Some(module)
+            }
+            Err(e) => {
+                warn!("rejected stale cache entry '{}': {e}", path.display());
+                self.remove(key);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: &str, module: &Module) -> error::Result<()> {
+        let bytes = module.serialize()?;
+        create_private_dir(&self.dir)?;
+
+        // Write to a temporary file first, so a partial write is never read back
+        let path = self.entry_path(key, MODULE_EXTENSION);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, &bytes)?;
+        fs::write(
+            self.entry_path(key, CHECKSUM_EXTENSION),
+            format!("{:x}", Sha256::digest(&bytes)),
+        )?;
+        fs::rename(tmp, path)?;
+
+        self.evict(key)?;
+        Ok(())
+    }
+
+    /// Remove the least recently used entries until the cache fits in `max_size`
+    fn evict(&self, keep: &str) -> io::Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(MODULE_EXTENSION) {
+                continue;
+            }
+
+            let metadata = fs::metadata(&path)?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+
+        let mut size = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+        entries.sort_by_key(|(modified, _, _)| *modified);
+
+        for (_, len, path) in entries {
+            if size <= self.max_size {
+                break;
+            }
+
+            let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            if key != keep {
+                debug!("evicting cache entry '{}'", path.display());
+                self.remove(key);
+                size -= len;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.entry_path(key, MODULE_EXTENSION));
+        let _ = fs::remove_file(self.entry_path(key, CHECKSUM_EXTENSION));
+    }
+
+    fn entry_path(&self, key: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{extension}"))
+    }
+}
+
+/// Feeds a [Hash] implementation into the digest, which unlike the std hashers
+/// is stable across Rust versions
+struct DigestHasher(Sha256);
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("the digest is read once hashing is done")
+    }
+}
+
+/// Create the directory and its parents, only letting the user access the directory
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Config;
+
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trap.wat")
+    }
+
+    fn entries(dir: &Path) -> Vec<PathBuf> {
+        let mut entries = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(MODULE_EXTENSION))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    fn engine(consume_fuel: bool) -> Engine {
+        let mut config = Config::new();
+        config.consume_fuel(consume_fuel);
+        Engine::new(&config).unwrap()
+    }
+
+    #[test]
+    fn should_reuse_cached_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf(), u64::MAX);
+        let engine = engine(false);
+
+        cache.load(&engine, &fixture()).unwrap();
+        let cached = entries(dir.path());
+        assert_eq!(cached.len(), 1);
+
+        cache.load(&engine, &fixture()).unwrap();
+        assert_eq!(entries(dir.path()), cached);
+    }
+
+    #[test]
+    fn should_key_by_engine_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf(), u64::MAX);
+
+        cache.load(&engine(false), &fixture()).unwrap();
+        cache.load(&engine(true), &fixture()).unwrap();
+
+        assert_eq!(entries(dir.path()).len(), 2);
+    }
+
+    #[test]
+    fn should_reject_corrupted_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf(), u64::MAX);
+        let engine = engine(false);
+
+        cache.load(&engine, &fixture()).unwrap();
+        let entry = entries(dir.path()).remove(0);
+        fs::write(&entry, b"not a module").unwrap();
+
+        cache.load(&engine, &fixture()).unwrap();
+        assert_ne!(fs::read(&entry).unwrap(), b"not a module");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_keep_dir_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().join("modules"), u64::MAX);
+        cache.load(&engine(false), &fixture()).unwrap();
+
+        let mode = fs::metadata(dir.path().join("modules"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn should_evict_least_recently_used_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf(), 1);
+
+        cache.load(&engine(false), &fixture()).unwrap();
+        cache.load(&engine(true), &fixture()).unwrap();
+
+        let key = ModuleCache::key(&engine(true), &fs::read(fixture()).unwrap());
+        assert_eq!(
+            entries(dir.path()),
+            vec![cache.entry_path(&key, MODULE_EXTENSION)]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::ops::RangeInclusive;
+
+use quelle_core::abi::ABI_VERSION;
+use serde::{Deserialize, Serialize};
+use wasmtime::Module;
+
+/// The ABI versions of the extensions the engine can load
+///
+/// Version 0 stands for extensions built before the version was exported. They
+/// exchange requests and responses as json and are rejected when loading.
+pub const SUPPORTED_ABI_VERSIONS: RangeInclusive<u32> = 1..=ABI_VERSION;
+
+/// The exports an extension may leave out
+pub const OPTIONAL_EXPORTS: &[&str] = &[
+    "abi_version",
+    "setup",
+    "set_level_filter",
+    "popular_url",
+    "popular",
+    "text_search_url",
+    "text_search",
+    "filter_options",
+    "filter_search_url",
+    "filter_search",
+    "image_request",
+];
+
+/// A group of functions an extension implements together
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trait {
+    /// Fetching a novel and the content of its chapters
+    Basic,
+    Popular,
+    TextSearch,
+    FilterSearch,
+    /// Describing the requests fetching the images of the source
+    Image,
+}
+
+impl Trait {
+    pub const ALL: [Trait; 5] = [
+        Trait::Basic,
+        Trait::Popular,
+        Trait::TextSearch,
+        Trait::FilterSearch,
+        Trait::Image,
+    ];
+
+    /// The optional exports the trait consists of
+    pub fn exports(&self) -> &'static [&'static str] {
+        match self {
+            Trait::Basic => &[],
+            Trait::Popular => &["popular_url", "popular"],
+            Trait::TextSearch => &["text_search_url", "text_search"],
+            Trait::FilterSearch => &["filter_options", "filter_search_url", "filter_search"],
+            Trait::Image => &["image_request"],
+        }
+    }
+}
+
+/// What an extension is able to do, known without calling into it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version of the ABI the extension was built against
+    pub abi_version: u32,
+    /// The optional exports the extension provides
+    pub exports: Vec<String>,
+    /// The traits the extension provides every export of
+    pub traits: Vec<Trait>,
+}
+
+impl Capabilities {
+    /// Collect the optional exports and traits of the module
+    pub fn of(module: &Module) -> Self {
+        let exports = OPTIONAL_EXPORTS
+            .iter()
+            .filter(|name| module.get_export(name).is_some())
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+
+        let traits = Trait::ALL
+            .into_iter()
+            .filter(|t| {
+                t.exports()
+                    .iter()
+                    .all(|name| exports.iter().any(|e| e == name))
+            })
+            .collect();
+
+        Self {
+            abi_version: 0,
+            exports,
+            traits,
+        }
+    }
+
+    #[inline]
+    pub fn supports(&self, t: Trait) -> bool {
+        self.traits.contains(&t)
+    }
+
+    #[inline]
+    pub fn exports(&self, name: &str) -> bool {
+        self.exports.iter().any(|export| export == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Engine;
+
+    use super::*;
+
+    #[test]
+    fn should_collect_complete_traits() {
+        let wat = r#"
+            (module
+              (func (export "fetch_novel"))
+              (func (export "popular_url"))
+              (func (export "popular"))
+              (func (export "filter_options")))
+        "#;
+        let module = Module::new(&Engine::default(), wat).unwrap();
+        let capabilities = Capabilities::of(&module);
+
+        assert_eq!(
+            capabilities.exports,
+            vec!["popular_url", "popular", "filter_options"]
+        );
+        assert_eq!(capabilities.traits, vec![Trait::Basic, Trait::Popular]);
+        assert!(capabilities.exports("filter_options"));
+        assert!(!capabilities.supports(Trait::FilterSearch));
+    }
+}
@@ -0,0 +1,289 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use quelle_core::prelude::{RateLimit, Request, RequestError, RequestErrorKind, Response};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    redirect, Certificate, Proxy,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{self, Error},
+    module::http::{build_request, parse_response},
+    network::PublicResolver,
+    rate_limit::RateLimiter,
+};
+
+/// Sends the requests of the extensions
+///
+/// The engine reads the requests from the guest memory and writes the responses
+/// back, so an implementation only deals with [Request] and [Response]. Every request
+/// goes through the cookies, rate limiter, [NetworkPolicy](crate::network::NetworkPolicy)
+/// and [RetryPolicy](crate::retry::RetryPolicy) of the extension first. The engine
+/// follows redirects on its own, so a client must return them as they are.
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    async fn send(&self, request: Request) -> Result<Response, RequestError>;
+
+    /// Send the request, refusing response bodies larger than `max_size` bytes
+    ///
+    /// Clients able to stop reading a body early should override this.
+    async fn send_limited(
+        &self,
+        request: Request,
+        max_size: Option<usize>,
+    ) -> Result<Response, RequestError> {
+        let response = self.send(request).await?;
+        match (max_size, &response.body) {
+            (Some(max_size), Some(body)) if body.len() > max_size => Err(RequestError {
+                kind: RequestErrorKind::TooLarge,
+                url: Some(response.url),
+                message: format!("the response body is larger than {max_size} bytes"),
+            }),
+            _ => Ok(response),
+        }
+    }
+}
+
+/// A reqwest client built by [HttpConfig::client]
+///
+/// Requests may shorten the timeout of the config for themselves, but never
+/// extend it.
+#[derive(Debug, Clone)]
+pub struct ConfiguredClient {
+    client: reqwest::Client,
+    timeout: Option<Duration>,
+}
+
+impl ConfiguredClient {
+    /// Turn the request into a reqwest request, capping its timeout
+    pub(crate) fn build(&self, request: Request) -> reqwest::RequestBuilder {
+        build_request(&self.client, request, self.timeout)
+    }
+}
+
+#[async_trait]
+impl HttpClient for ConfiguredClient {
+    async fn send(&self, request: Request) -> Result<Response, RequestError> {
+        self.send_limited(request, None).await
+    }
+
+    async fn send_limited(
+        &self,
+        request: Request,
+        max_size: Option<usize>,
+    ) -> Result<Response, RequestError> {
+        let response = self.build(request).send().await;
+        parse_response(response, max_size).await
+    }
+}
+
+/// The options of the reqwest client sending the requests of the extensions
+///
+/// Every field may be left out when deserialized, falling back to its default.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub user_agent: String,
+
+    /// The time in milliseconds a request may take, unless the request sets a shorter one
+    pub timeout: Option<u64>,
+
+    /// The time in milliseconds connecting to a host may take
+    pub connect_timeout: Option<u64>,
+
+    /// The url of an `http`, `https`, `socks5` or `socks5h` proxy for every request
+    pub proxy: Option<String>,
+
+    /// Headers sent with every request, unless the request sets them itself
+    pub headers: BTreeMap<String, String>,
+
+    /// PEM files of certificates to trust on top of the system ones
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// Connect to domains resolving to loopback or private addresses
+    ///
+    /// Otherwise those addresses are dropped when resolving, see [PublicResolver].
+    /// Behind a proxy the proxy resolves the domains instead.
+    pub allow_private: bool,
+
+    /// The limit of the requests to every site
+    pub rate_limit: RateLimit,
+
+    /// Stricter limits for single sites, keyed by one of their hosts
+    ///
+    /// Hosts of the same registrable domain share a limit, see [RateLimiter].
+    pub rate_limits: BTreeMap<String, RateLimit>,
+
+    pub gzip: bool,
+    pub brotli: bool,
+    pub deflate: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: String::from(
+                "Mozilla/5.0 (X11; Fedora; Linux x86_64; rv:107.0) Gecko/20100101 Firefox/107.0",
+            ),
+            timeout: Some(30_000),
+            connect_timeout: Some(10_000),
+            proxy: None,
+            headers: BTreeMap::new(),
+            ca_certificates: Vec::new(),
+            allow_private: false,
+            rate_limit: RateLimit::default(),
+            rate_limits: BTreeMap::new(),
+            gzip: true,
+            brotli: true,
+            deflate: true,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Build a client with the options, leaving redirects to the engine
+    pub fn client(&self) -> error::Result<ConfiguredClient> {
+        let invalid = |e: &dyn std::fmt::Display| Error::InvalidHttpConfig(e.to_string());
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::try_from(name).map_err(|e| invalid(&e))?;
+            let value = HeaderValue::try_from(value).map_err(|e| invalid(&e))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(&self.user_agent)
+            .default_headers(headers)
+            .redirect(redirect::Policy::none())
+            .gzip(self.gzip)
+            .brotli(self.brotli)
+            .deflate(self.deflate);
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(Duration::from_millis(timeout));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_millis(timeout));
+        }
+        match &self.proxy {
+            Some(proxy) => builder = builder.proxy(Proxy::all(proxy).map_err(|e| invalid(&e))?),
+            None if !self.allow_private => builder = builder.dns_resolver(Arc::new(PublicResolver)),
+            None => {}
+        }
+        for path in &self.ca_certificates {
+            let pem = fs::read(path)
+                .map_err(|e| invalid(&format!("failed to read '{}': {e}", path.display())))?;
+            builder =
+                builder.add_root_certificate(Certificate::from_pem(&pem).map_err(|e| invalid(&e))?);
+        }
+
+        Ok(ConfiguredClient {
+            client: builder.build().map_err(|e| invalid(&e))?,
+            timeout: self.timeout.map(Duration::from_millis),
+        })
+    }
+
+    /// Schedule the requests by the configured rate limits
+    pub fn rate_limiter(&self) -> RateLimiter {
+        self.rate_limits.iter().fold(
+            RateLimiter::new(self.rate_limit.clone()),
+            |limiter, (host, limit)| limiter.host(host, limit.clone()),
+        )
+    }
+}
+
+/// Fails every request, for runtimes built without a client
+pub(crate) struct Unsupported;
+
+#[async_trait]
+impl HttpClient for Unsupported {
+    async fn send(&self, request: Request) -> Result<Response, RequestError> {
+        Err(RequestError {
+            kind: RequestErrorKind::Unknown,
+            url: Some(request.url),
+            message: String::from("the runtime can not send requests"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    #[async_trait]
+    impl HttpClient for Echo {
+        async fn send(&self, request: Request) -> Result<Response, RequestError> {
+            Ok(Response {
+                status: 200,
+                url: request.url.clone(),
+                body: Some(request.url.into_bytes()),
+                headers: Default::default(),
+            })
+        }
+    }
+
+    #[test]
+    fn should_build_client_from_config() {
+        let config = r#"{"user_agent": "quelle", "proxy": "socks5h://127.0.0.1:9050"}"#;
+        let config = serde_json::from_str::<HttpConfig>(config).unwrap();
+        assert_eq!(config.user_agent, "quelle");
+        assert_eq!(config.timeout, HttpConfig::default().timeout);
+        assert!(config.client().is_ok());
+
+        let mut invalid = HttpConfig::default();
+        invalid
+            .headers
+            .insert(String::from("bad header"), String::from("value"));
+        assert!(matches!(invalid.client(), Err(Error::InvalidHttpConfig(_))));
+
+        let mut missing = HttpConfig::default();
+        missing
+            .ca_certificates
+            .push(PathBuf::from("/does/not/exist.pem"));
+        assert!(matches!(missing.client(), Err(Error::InvalidHttpConfig(_))));
+    }
+
+    #[test]
+    fn should_build_rate_limiter_from_config() {
+        let config = r#"{
+            "rate_limit": {"interval": 500},
+            "rate_limits": {"www.example.com": {"interval": 2000, "concurrency": 1}}
+        }"#;
+        let limiter = serde_json::from_str::<HttpConfig>(config)
+            .unwrap()
+            .rate_limiter();
+
+        let expected = RateLimit {
+            interval: Some(2000),
+            concurrency: Some(1),
+            jitter: None,
+        };
+        assert_eq!(limiter.limit("chapters.example.com"), expected);
+        assert_eq!(limiter.limit("example.org").interval, Some(500));
+    }
+
+    #[tokio::test]
+    async fn should_refuse_domains_of_private_addresses() {
+        let client = HttpConfig::default().client().unwrap();
+        let request = Request::get(String::from("http://localhost:1/"));
+
+        let error = HttpClient::send(&client, request).await.unwrap_err();
+        assert!(matches!(error.kind, RequestErrorKind::Blocked));
+    }
+
+    #[tokio::test]
+    async fn should_refuse_large_bodies() {
+        let request = Request::get(String::from("https://example.com/page"));
+
+        let response = Echo.send_limited(request.clone(), Some(64)).await;
+        assert_eq!(response.unwrap().url, "https://example.com/page");
+
+        let error = Echo.send_limited(request, Some(8)).await.unwrap_err();
+        assert!(matches!(error.kind, RequestErrorKind::TooLarge));
+    }
+}
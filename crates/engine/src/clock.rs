@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+
+type Now = dyn Fn() -> DateTime<Utc> + Send + Sync;
+
+/// The current time and timezone handed to the extension
+///
+/// Reads the system clock and the local timezone of the host by default.
+/// Use [Clock::fixed] to give the extension the same time on every call.
+#[derive(Clone)]
+pub struct Clock {
+    now: Arc<Now>,
+    offset: Option<FixedOffset>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(Utc::now)
+    }
+}
+
+impl Clock {
+    /// Read the current time from the function
+    pub fn new<F>(now: F) -> Self
+    where
+        F: Fn() -> DateTime<Utc> + Send + Sync + 'static,
+    {
+        Self {
+            now: Arc::new(now),
+            offset: None,
+        }
+    }
+
+    /// Always return the same time
+    pub fn fixed(now: DateTime<Utc>) -> Self {
+        Self::new(move || now)
+    }
+
+    /// Use the offset as the timezone of the user instead of the local one
+    pub fn offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        (self.now)()
+    }
+
+    /// The offset of the timezone of the user at the current time
+    pub fn utc_offset(&self) -> FixedOffset {
+        match self.offset {
+            Some(offset) => offset,
+            None => *self.now().with_timezone(&Local).offset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn should_return_the_fixed_time() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap();
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+        let clock = Clock::fixed(now).offset(offset);
+
+        assert_eq!(clock.now(), now);
+        assert_eq!(clock.clone().now(), now);
+        assert_eq!(clock.utc_offset().local_minus_utc(), 7200);
+    }
+}
@@ -0,0 +1,365 @@
+//! Conversions between the types of the WIT world and the types of `quelle_core`
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use quelle_core::prelude as core;
+
+use super::{
+    guest_log::Level,
+    storage::StorageError,
+    types::{
+        Attribute, Body, Chapter, Content, Meta, Metadata, Method, Namespace, Novel, NovelStatus,
+        ParseError, QuelleError, RateLimit, ReadingDirection, Request, RequestError,
+        RequestErrorKind, Response, TaggedDateTime, Volume,
+    },
+    BasicNovel,
+};
+
+impl From<Meta> for core::Meta {
+    fn from(value: Meta) -> Self {
+        core::Meta {
+            id: value.id,
+            name: value.name,
+            langs: value.langs,
+            version: value.version,
+            base_urls: value.base_urls,
+            rds: value.rds.into_iter().map(Into::into).collect(),
+            attrs: value.attrs.into_iter().map(Into::into).collect(),
+            rate_limit: value.rate_limit.map(Into::into),
+            extra_hosts: value.extra_hosts,
+        }
+    }
+}
+
+impl From<ReadingDirection> for core::ReadingDirection {
+    fn from(value: ReadingDirection) -> Self {
+        match value {
+            ReadingDirection::Ltr => core::ReadingDirection::Ltr,
+            ReadingDirection::Rtl => core::ReadingDirection::Rtl,
+        }
+    }
+}
+
+impl From<Attribute> for core::Attribute {
+    fn from(value: Attribute) -> Self {
+        match value {
+            Attribute::Fanfiction => core::Attribute::Fanfiction,
+        }
+    }
+}
+
+impl From<RateLimit> for core::RateLimit {
+    fn from(value: RateLimit) -> Self {
+        core::RateLimit {
+            interval: value.interval,
+            concurrency: value.concurrency.map(|v| v as usize),
+            jitter: value.jitter,
+        }
+    }
+}
+
+impl From<Novel> for core::Novel {
+    fn from(value: Novel) -> Self {
+        core::Novel {
+            url: value.url,
+            authors: value.authors,
+            title: value.title,
+            cover: value.cover,
+            description: value.description,
+            volumes: value.volumes.into_iter().map(Into::into).collect(),
+            metadata: value.metadata.into_iter().map(Into::into).collect(),
+            status: value.status.into(),
+            langs: value.langs,
+        }
+    }
+}
+
+impl From<Volume> for core::Volume {
+    fn from(value: Volume) -> Self {
+        core::Volume {
+            index: value.index,
+            name: value.name,
+            chapters: value.chapters.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Chapter> for core::Chapter {
+    fn from(value: Chapter) -> Self {
+        core::Chapter {
+            index: value.index,
+            title: value.title,
+            url: value.url,
+            updated_at: value.updated_at.and_then(tagged_date_time),
+        }
+    }
+}
+
+/// Parse the date, dropping it when the extension sent an invalid one
+fn tagged_date_time(value: TaggedDateTime) -> Option<core::TaggedDateTime> {
+    let parsed = match &value {
+        TaggedDateTime::Utc(value) => DateTime::parse_from_rfc3339(value)
+            .map(|date| core::TaggedDateTime::Utc(date.with_timezone(&Utc)))
+            .ok(),
+        TaggedDateTime::Local(value) => value
+            .parse::<NaiveDateTime>()
+            .map(core::TaggedDateTime::Local)
+            .ok(),
+    };
+
+    if parsed.is_none() {
+        log::warn!("dropped invalid chapter date {value:?}");
+    }
+    parsed
+}
+
+impl From<Metadata> for core::Metadata {
+    fn from(value: Metadata) -> Self {
+        core::Metadata {
+            name: value.name,
+            value: value.value,
+            ns: match value.ns {
+                Namespace::Dc => core::Namespace::DC,
+                Namespace::Opf => core::Namespace::OPF,
+            },
+            others: value.others.into_iter().collect(),
+        }
+    }
+}
+
+impl From<NovelStatus> for core::NovelStatus {
+    fn from(value: NovelStatus) -> Self {
+        match value {
+            NovelStatus::Ongoing => core::NovelStatus::Ongoing,
+            NovelStatus::Hiatus => core::NovelStatus::Hiatus,
+            NovelStatus::Completed => core::NovelStatus::Completed,
+            NovelStatus::Stub => core::NovelStatus::Stub,
+            NovelStatus::Dropped => core::NovelStatus::Dropped,
+            NovelStatus::Unknown => core::NovelStatus::Unknown,
+        }
+    }
+}
+
+impl From<BasicNovel> for core::BasicNovel {
+    fn from(value: BasicNovel) -> Self {
+        core::BasicNovel {
+            title: value.title,
+            cover: value.cover,
+            url: value.url,
+        }
+    }
+}
+
+impl From<Content> for core::Content {
+    fn from(value: Content) -> Self {
+        core::Content { data: value.data }
+    }
+}
+
+impl From<QuelleError> for core::QuelleError {
+    fn from(value: QuelleError) -> Self {
+        match value {
+            QuelleError::RequestFailed(error) => {
+                core::QuelleError::RequestFailed(core::RequestError::from(error).into())
+            }
+            QuelleError::FilterVerificationFailed(message) => {
+                core::QuelleError::FilterVerificationFailed(message)
+            }
+            QuelleError::Utf8Error => core::QuelleError::Utf8Error,
+            QuelleError::ParseFailed(error) => core::QuelleError::ParseFailed(error.into()),
+            QuelleError::WasmAbiError(message) => core::QuelleError::WasmAbiError(message),
+            QuelleError::DecodeFailed(message) => core::QuelleError::DecodeFailed(message),
+        }
+    }
+}
+
+impl From<ParseError> for core::ParseError {
+    fn from(value: ParseError) -> Self {
+        match value {
+            ParseError::ElementNotFound => core::ParseError::ElementNotFound,
+            ParseError::SerializeFailed => core::ParseError::SerializeFailed,
+            ParseError::FailedUrlParse => core::ParseError::FailedURLParse,
+            ParseError::FailedJsonParse => core::ParseError::FailedJsonParse,
+            ParseError::ParseIntError => core::ParseError::ParseIntError,
+            ParseError::Other(message) => core::ParseError::Other(message),
+        }
+    }
+}
+
+impl From<Request> for core::Request {
+    fn from(value: Request) -> Self {
+        core::Request {
+            method: match value.method {
+                Method::Get => core::Method::Get,
+                Method::Post => core::Method::Post,
+                Method::Put => core::Method::Put,
+                Method::Patch => core::Method::Patch,
+                Method::Delete => core::Method::Delete,
+            },
+            url: value.url,
+            params: value.params,
+            data: value.data.map(|body| match body {
+                Body::Form(data) => core::Body::Form(data.into_iter().collect()),
+                Body::UrlEncoded(data) => core::Body::UrlEncoded(data.into_iter().collect()),
+                Body::Json(data) => core::Body::Json(data),
+                Body::Bytes(body) => core::Body::Bytes {
+                    content_type: body.content_type,
+                    data: body.data,
+                },
+            }),
+            headers: value.headers,
+            timeout: value.timeout,
+        }
+    }
+}
+
+impl From<core::Response> for Response {
+    fn from(value: core::Response) -> Self {
+        Response {
+            status: value.status as u16,
+            headers: value
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            url: value.url,
+            body: value.body,
+        }
+    }
+}
+
+impl From<core::RequestError> for RequestError {
+    fn from(value: core::RequestError) -> Self {
+        RequestError {
+            kind: match value.kind {
+                core::RequestErrorKind::Serial => RequestErrorKind::Serial,
+                core::RequestErrorKind::Request => RequestErrorKind::Request,
+                core::RequestErrorKind::Redirect => RequestErrorKind::Redirect,
+                core::RequestErrorKind::Status(status) => RequestErrorKind::Status(status),
+                core::RequestErrorKind::Body => RequestErrorKind::Body,
+                core::RequestErrorKind::Timeout => RequestErrorKind::Timeout,
+                core::RequestErrorKind::Unknown => RequestErrorKind::Unknown,
+                core::RequestErrorKind::Connect => RequestErrorKind::Connect,
+                core::RequestErrorKind::Blocked => RequestErrorKind::Blocked,
+                core::RequestErrorKind::TooLarge => RequestErrorKind::TooLarge,
+            },
+            url: value.url,
+            message: value.message,
+        }
+    }
+}
+
+impl From<RequestError> for core::RequestError {
+    fn from(value: RequestError) -> Self {
+        core::RequestError {
+            kind: match value.kind {
+                RequestErrorKind::Serial => core::RequestErrorKind::Serial,
+                RequestErrorKind::Request => core::RequestErrorKind::Request,
+                RequestErrorKind::Redirect => core::RequestErrorKind::Redirect,
+                RequestErrorKind::Status(status) => core::RequestErrorKind::Status(status),
+                RequestErrorKind::Body => core::RequestErrorKind::Body,
+                RequestErrorKind::Timeout => core::RequestErrorKind::Timeout,
+                RequestErrorKind::Unknown => core::RequestErrorKind::Unknown,
+                RequestErrorKind::Connect => core::RequestErrorKind::Connect,
+                RequestErrorKind::Blocked => core::RequestErrorKind::Blocked,
+                RequestErrorKind::TooLarge => core::RequestErrorKind::TooLarge,
+            },
+            url: value.url,
+            message: value.message,
+        }
+    }
+}
+
+impl From<core::StorageError> for StorageError {
+    fn from(value: core::StorageError) -> Self {
+        match value {
+            core::StorageError::Unavailable(message) => StorageError::Unavailable(message),
+            core::StorageError::QuotaExceeded(message) => StorageError::QuotaExceeded(message),
+            core::StorageError::Io(message) => StorageError::Io(message),
+            core::StorageError::Serial => StorageError::Serial,
+        }
+    }
+}
+
+impl From<Level> for log::Level {
+    fn from(value: Level) -> Self {
+        match value {
+            Level::Error => log::Level::Error,
+            Level::Warn => log::Level::Warn,
+            Level::Info => log::Level::Info,
+            Level::Debug => log::Level::Debug,
+            Level::Trace => log::Level::Trace,
+        }
+    }
+}
+
+impl From<log::Level> for Level {
+    fn from(value: log::Level) -> Self {
+        match value {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::types::BytesBody;
+
+    #[test]
+    fn should_convert_guest_request() {
+        let request = Request {
+            method: Method::Post,
+            url: String::from("https://example.com/search"),
+            params: Some(vec![(String::from("page"), String::from("2"))]),
+            data: Some(Body::Bytes(BytesBody {
+                content_type: String::from("text/plain"),
+                data: b"hello".to_vec(),
+            })),
+            headers: None,
+            timeout: Some(5_000),
+        };
+
+        let request = core::Request::from(request);
+        assert!(matches!(request.method, core::Method::Post));
+        assert_eq!(request.params.unwrap()[0].1, "2");
+        assert_eq!(request.timeout, Some(5_000));
+        assert!(matches!(
+            request.data,
+            Some(core::Body::Bytes { data, .. }) if data == b"hello"
+        ));
+    }
+
+    #[test]
+    fn should_parse_chapter_dates() {
+        let chapter = |updated_at| Chapter {
+            index: 0,
+            title: String::new(),
+            url: String::new(),
+            updated_at: Some(updated_at),
+        };
+
+        let utc = core::Chapter::from(chapter(TaggedDateTime::Utc(String::from(
+            "2024-03-01T10:00:00+02:00",
+        ))));
+        assert!(matches!(
+            utc.updated_at,
+            Some(core::TaggedDateTime::Utc(date)) if date.to_rfc3339() == "2024-03-01T08:00:00+00:00"
+        ));
+
+        let local = core::Chapter::from(chapter(TaggedDateTime::Local(String::from(
+            "2024-03-01T10:00:00",
+        ))));
+        assert!(matches!(
+            local.updated_at,
+            Some(core::TaggedDateTime::Local(_))
+        ));
+
+        let invalid =
+            core::Chapter::from(chapter(TaggedDateTime::Local(String::from("yesterday"))));
+        assert!(invalid.updated_at.is_none());
+    }
+}
@@ -0,0 +1,703 @@
+//! Extensions built against the WIT world in `wit/extension.wit`
+//!
+//! The world replaces the hand written ABI of [Runtime](crate::Runtime), where
+//! every value crosses the boundary as json through pointers and a stack. Both
+//! kinds of extensions can be loaded while the extensions are migrated, see
+//! [is_component] to tell them apart.
+
+mod convert;
+
+use std::{future::Future, path::Path, time::Duration};
+
+use log::LevelFilter;
+use quelle_core::abi::ABI_VERSION;
+use quelle_core::prelude::{BasicNovel as CoreBasicNovel, *};
+use wasmtime::{
+    component::{Component, Instance, Linker},
+    Engine, Store,
+};
+
+use crate::{
+    capabilities::{Capabilities, Trait},
+    cookie::{CookieJar, CookieStore},
+    data::State,
+    error::{self, AffectedFunction, Error},
+    limits::{Limit, Limits},
+    module,
+    output::Stream,
+    reset_limits,
+};
+
+wasmtime::component::bindgen!({
+    path: "../../wit",
+    world: "extension",
+    async: true,
+});
+
+use exports::quelle::extension::{filter_search, image, popular, source, text_search};
+use quelle::extension::{clock, http, log as guest_log, storage, types};
+pub use types::BasicNovel;
+
+pub(crate) const SOURCE: &str = "quelle:extension/source@0.1.0";
+const POPULAR: &str = "quelle:extension/popular@0.1.0";
+const TEXT_SEARCH: &str = "quelle:extension/text-search@0.1.0";
+const FILTER_SEARCH: &str = "quelle:extension/filter-search@0.1.0";
+const IMAGE: &str = "quelle:extension/image@0.1.0";
+
+/// Whether the bytes are a component rather than a core module
+///
+/// Accepts both the binary and the text format.
+pub fn is_component(bytes: &[u8]) -> bool {
+    match bytes {
+        // The layer field following the magic and version is 1 for components
+        [0, b'a', b's', b'm', _, _, layer, 0, ..] => *layer == 1,
+        _ => {
+            std::str::from_utf8(bytes).is_ok_and(|text| text.trim_start().starts_with("(component"))
+        }
+    }
+}
+
+impl<D: Send> types::Host for State<D> {}
+
+/// Requests are answered like the ones of modules, see [module::http::respond]
+#[async_trait::async_trait]
+impl<D: Send + Sync> http::Host for State<D> {
+    async fn send_request(
+        &mut self,
+        request: types::Request,
+    ) -> wasmtime::Result<Result<types::Response, types::RequestError>> {
+        let response = module::http::respond(self, request.into()).await?;
+        Ok(response.map(Into::into).map_err(Into::into))
+    }
+
+    async fn send_requests(
+        &mut self,
+        requests: Vec<types::Request>,
+    ) -> wasmtime::Result<Vec<Result<types::Response, types::RequestError>>> {
+        let requests = requests.into_iter().map(Request::from).collect();
+        let responses = module::http::send_all(self, requests).await?;
+
+        Ok(responses
+            .into_iter()
+            .map(|response| response.map(Into::into).map_err(Into::into))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Send> guest_log::Host for State<D> {
+    async fn log(&mut self, event: guest_log::Event) -> wasmtime::Result<()> {
+        let event = LogEvent {
+            level: event.level.into(),
+            args: event.message,
+            module_path: event.module_path.as_deref(),
+            file: event.file.as_deref(),
+            line: event.line,
+        };
+        module::log::forward(self, &event);
+        Ok(())
+    }
+
+    async fn print(&mut self, text: String) -> wasmtime::Result<()> {
+        self.output().write(Stream::Stdout, &text);
+        Ok(())
+    }
+
+    async fn eprint(&mut self, text: String) -> wasmtime::Result<()> {
+        self.output().write(Stream::Stderr, &text);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Send> clock::Host for State<D> {
+    async fn now(&mut self) -> wasmtime::Result<i64> {
+        Ok(self.clock().now().timestamp_millis())
+    }
+
+    async fn utc_offset(&mut self) -> wasmtime::Result<i32> {
+        Ok(self.clock().utc_offset().local_minus_utc())
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Send> storage::Host for State<D> {
+    async fn get(
+        &mut self,
+        key: String,
+    ) -> wasmtime::Result<Result<Option<String>, storage::StorageError>> {
+        let result = module::storage::namespace(self).map(|namespace| namespace.get(&key));
+        Ok(result.map_err(Into::into))
+    }
+
+    async fn set(
+        &mut self,
+        key: String,
+        value: String,
+    ) -> wasmtime::Result<Result<(), storage::StorageError>> {
+        let result =
+            module::storage::namespace(self).and_then(|namespace| namespace.set(&key, &value));
+        Ok(result.map_err(Into::into))
+    }
+
+    async fn delete(&mut self, key: String) -> wasmtime::Result<Result<(), storage::StorageError>> {
+        let result = module::storage::namespace(self).and_then(|namespace| namespace.delete(&key));
+        Ok(result.map_err(Into::into))
+    }
+}
+
+/// The interfaces exported by an instance of the component
+struct Exports {
+    source: source::Guest,
+    popular: Option<popular::Guest>,
+    text_search: Option<text_search::Guest>,
+    filter_search: Option<filter_search::Guest>,
+    image: Option<image::Guest>,
+}
+
+impl Exports {
+    fn capabilities(&self) -> Capabilities {
+        let mut traits = vec![Trait::Basic];
+        traits.extend(self.popular.as_ref().map(|_| Trait::Popular));
+        traits.extend(self.text_search.as_ref().map(|_| Trait::TextSearch));
+        traits.extend(self.filter_search.as_ref().map(|_| Trait::FilterSearch));
+        traits.extend(self.image.as_ref().map(|_| Trait::Image));
+
+        // Named after the exports of the legacy ABI, so both kinds compare equal
+        let exports = ["abi_version", "setup", "set_level_filter"]
+            .into_iter()
+            .chain(traits.iter().flat_map(|t| t.exports().iter().copied()))
+            .map(String::from)
+            .collect();
+
+        Capabilities {
+            abi_version: ABI_VERSION,
+            exports,
+            traits,
+        }
+    }
+}
+
+/// Create a new instance of the component along with its exported interfaces
+async fn instantiate<D: Send>(
+    linker: &Linker<State<D>>,
+    component: &Component,
+    store: &mut Store<State<D>>,
+) -> error::Result<(Instance, Exports)> {
+    let instance = linker.instantiate_async(&mut *store, component).await?;
+
+    let mut exports = instance.exports(&mut *store);
+    let mut root = exports.root();
+
+    let source = match root.instance(SOURCE) {
+        Some(mut instance) => source::Guest::new(&mut instance)?,
+        None => return Err(Error::MissingExport(String::from(SOURCE))),
+    };
+
+    macro_rules! optional {
+        ($interface:ident, $name:expr) => {
+            root.instance($name)
+                .map(|mut instance| $interface::Guest::new(&mut instance))
+                .transpose()?
+        };
+    }
+
+    let exports = Exports {
+        source,
+        popular: optional!(popular, POPULAR),
+        text_search: optional!(text_search, TEXT_SEARCH),
+        filter_search: optional!(filter_search, FILTER_SEARCH),
+        image: optional!(image, IMAGE),
+    };
+
+    Ok((instance, exports))
+}
+
+/// Call a function of an exported interface, checking the result with [ComponentRuntime::check]
+macro_rules! call {
+    ($self:ident, $interface:expr, $func:ident($($arg:expr),*)) => {{
+        let store = $self.store.as_mut().expect(STORE_TAKEN);
+        let call = $interface.$func(store, $($arg),*);
+        let result = bounded($self.limits.deadline, call).await;
+        $self.check(result)
+    }};
+}
+
+const STORE_TAKEN: &str = "the store is only taken while recovering";
+
+/// Bound the call by the deadline, returning `None` once it elapsed
+///
+/// The epoch deadline only interrupts guest code, so this also stops a call
+/// waiting on slow host calls.
+async fn bounded<T>(
+    deadline: Option<Duration>,
+    call: impl Future<Output = wasmtime::Result<T>>,
+) -> Option<wasmtime::Result<T>> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, call).await.ok(),
+        None => Some(call.await),
+    }
+}
+
+fn level(level_filter: LevelFilter) -> Option<guest_log::Level> {
+    level_filter.to_level().map(Into::into)
+}
+
+/// Runs an extension built as a component
+///
+/// Offers the same calls as [Runtime](crate::Runtime), built with
+/// [RuntimeBuilder::build_component](crate::RuntimeBuilder::build_component).
+pub struct ComponentRuntime<D> {
+    engine: Engine,
+    component: Component,
+    linker: Linker<State<D>>,
+    /// Only taken out while a new store is created on [ComponentRuntime::recover]
+    store: Option<Store<State<D>>>,
+    #[allow(dead_code)]
+    instance: Instance,
+    exports: Exports,
+    limits: Limits,
+    cookie_store: CookieStore,
+    capabilities: Capabilities,
+    /// The level filter of the last successful setup
+    setup_level: Option<LevelFilter>,
+    /// Whether the last call failed and the instance must be replaced before the next call
+    poisoned: bool,
+}
+
+impl<D> ComponentRuntime<D>
+where
+    D: Send + Sync,
+{
+    pub(crate) async fn new(
+        engine: &Engine,
+        path: &Path,
+        limits: Limits,
+        cookie_store: CookieStore,
+        state: State<D>,
+    ) -> error::Result<Self> {
+        let component = Component::from_file(engine, path)?;
+
+        let mut linker = Linker::new(engine);
+        Extension::add_to_linker(&mut linker, |state| state)?;
+
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limiter);
+        reset_limits(&mut store, &limits)?;
+
+        let (instance, exports) = instantiate(&linker, &component, &mut store).await?;
+        let capabilities = exports.capabilities();
+
+        Ok(Self {
+            engine: engine.clone(),
+            component,
+            linker,
+            store: Some(store),
+            instance,
+            exports,
+            limits,
+            cookie_store,
+            capabilities,
+            setup_level: None,
+            poisoned: false,
+        })
+    }
+
+    /// The traits the extension exports an interface for
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// The cookies of the extension, to inspect or clear them
+    pub fn cookie_jar(&self) -> &CookieJar {
+        self.store().data().cookie_jar()
+    }
+
+    /// Whether the last call failed, leaving the instance to be replaced on the next call
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Read the extension's meta
+    ///
+    /// Later requests use the rate limit hinted by the meta and the cookies
+    /// kept for the extension id.
+    pub async fn meta(&mut self) -> error::Result<Meta> {
+        self.prepare().await?;
+        let meta = Meta::from(call!(self, self.exports.source, call_meta())?);
+
+        let state = self.store().data();
+        state.rate_limiter().hint(&meta);
+        state.network().hint(&meta);
+        state.mirrors().hint(&meta);
+
+        let cookie_jar = self.cookie_store.bind(&meta.id, &state.cookie_jar);
+        let state = self.store_mut().data_mut();
+        state.cookie_jar = cookie_jar;
+        state.extension_id = Some(meta.id.clone());
+        Ok(meta)
+    }
+
+    /// Call the extension's setup function
+    pub async fn setup(&mut self, config: &ExtensionConfig) -> error::Result<()> {
+        self.prepare().await?;
+        self.call_setup(config.level_filter).await?;
+        self.setup_level = Some(config.level_filter);
+        self.store_mut().data_mut().level_filter = None;
+        Ok(())
+    }
+
+    /// Change the level of the logs the extension sends without running the setup again
+    pub async fn set_level_filter(&mut self, level_filter: LevelFilter) -> error::Result<()> {
+        self.prepare().await?;
+        self.store_mut().data_mut().level_filter = Some(level_filter);
+        self.apply_level_filter().await
+    }
+
+    async fn apply_level_filter(&mut self) -> error::Result<()> {
+        let Some(level_filter) = self.store().data().level_filter else {
+            return Ok(());
+        };
+
+        call!(
+            self,
+            self.exports.source,
+            call_set_level_filter(level(level_filter))
+        )
+    }
+
+    async fn call_setup(&mut self, level_filter: LevelFilter) -> error::Result<()> {
+        call!(self, self.exports.source, call_setup(level(level_filter)))
+    }
+
+    pub async fn fetch_novel(&mut self, url: &str) -> error::Result<Novel> {
+        self.prepare().await?;
+        returned(call!(self, self.exports.source, call_fetch_novel(url))?)
+    }
+
+    pub async fn fetch_chapter_content(&mut self, url: &str) -> error::Result<Content> {
+        self.prepare().await?;
+        returned(call!(
+            self,
+            self.exports.source,
+            call_fetch_chapter_content(url)
+        )?)
+    }
+
+    // --------------------------------------------------------------------------------
+    // Popular
+    // --------------------------------------------------------------------------------
+
+    pub fn popular_supported(&self) -> bool {
+        self.capabilities.supports(Trait::Popular)
+    }
+
+    pub async fn popular_url(&mut self, page: i32) -> error::Result<String> {
+        self.prepare().await?;
+        let Some(popular) = &self.exports.popular else {
+            return Err(Error::NotSupported(AffectedFunction::PopularUrl));
+        };
+
+        call!(self, popular, call_popular_url(page))
+    }
+
+    pub async fn popular(&mut self, page: i32) -> error::Result<Vec<CoreBasicNovel>> {
+        self.prepare().await?;
+        let Some(popular) = &self.exports.popular else {
+            return Err(Error::NotSupported(AffectedFunction::Popular));
+        };
+
+        novels(call!(self, popular, call_popular(page))?)
+    }
+
+    // --------------------------------------------------------------------------------
+    // Text search
+    // --------------------------------------------------------------------------------
+
+    pub fn text_search_supported(&self) -> bool {
+        self.capabilities.supports(Trait::TextSearch)
+    }
+
+    pub async fn text_search_url(&mut self, query: &str, page: i32) -> error::Result<String> {
+        self.prepare().await?;
+        let Some(text_search) = &self.exports.text_search else {
+            return Err(Error::NotSupported(AffectedFunction::TextSearchUrl));
+        };
+
+        returned(call!(self, text_search, call_text_search_url(query, page))?)
+    }
+
+    pub async fn text_search(
+        &mut self,
+        query: &str,
+        page: i32,
+    ) -> error::Result<Vec<CoreBasicNovel>> {
+        self.prepare().await?;
+        let Some(text_search) = &self.exports.text_search else {
+            return Err(Error::NotSupported(AffectedFunction::TextSearch));
+        };
+
+        novels(call!(self, text_search, call_text_search(query, page))?)
+    }
+
+    // --------------------------------------------------------------------------------
+    // Filter search
+    // --------------------------------------------------------------------------------
+
+    pub fn filter_search_supported(&self) -> bool {
+        self.capabilities.supports(Trait::FilterSearch)
+    }
+
+    pub async fn filter_options(&mut self) -> error::Result<FieldMap> {
+        self.prepare().await?;
+        let Some(filter_search) = &self.exports.filter_search else {
+            return Err(Error::NotSupported(AffectedFunction::FilterOptions));
+        };
+
+        let options = call!(self, filter_search, call_filter_options())?;
+        serde_json::from_str(&options).map_err(|_| Error::DeserializeError)
+    }
+
+    pub async fn filter_search_url(&mut self, params: &str, page: i32) -> error::Result<String> {
+        self.prepare().await?;
+        let Some(filter_search) = &self.exports.filter_search else {
+            return Err(Error::NotSupported(AffectedFunction::FilterSearchUrl));
+        };
+
+        returned(call!(
+            self,
+            filter_search,
+            call_filter_search_url(params, page)
+        )?)
+    }
+
+    pub async fn filter_search(
+        &mut self,
+        params: &str,
+        page: i32,
+    ) -> error::Result<Vec<CoreBasicNovel>> {
+        self.prepare().await?;
+        let Some(filter_search) = &self.exports.filter_search else {
+            return Err(Error::NotSupported(AffectedFunction::FilterSearch));
+        };
+
+        novels(call!(
+            self,
+            filter_search,
+            call_filter_search(params, page)
+        )?)
+    }
+
+    // --------------------------------------------------------------------------------
+    // Images
+    // --------------------------------------------------------------------------------
+
+    pub fn image_request_supported(&self) -> bool {
+        self.capabilities.supports(Trait::Image)
+    }
+
+    pub async fn image_request(&mut self, url: &str) -> error::Result<Request> {
+        self.prepare().await?;
+        let Some(image) = &self.exports.image else {
+            return Err(Error::NotSupported(AffectedFunction::ImageRequest));
+        };
+
+        returned(call!(self, image, call_image_request(url))?)
+    }
+
+    /// Fetch the image at the url, see [Runtime::fetch_image](crate::Runtime::fetch_image)
+    pub async fn fetch_image(&mut self, url: &str) -> error::Result<Response> {
+        let request = match self.image_request(url).await {
+            Err(Error::NotSupported(_)) => Request::get(url.to_string()),
+            result => result?,
+        };
+        module::http::send_image(self.store().data(), request).await
+    }
+
+    // --------------------------------------------------------------------------------
+    // Helpers
+    // --------------------------------------------------------------------------------
+
+    /// Recover from a previous failure and refill the limits before a new call into the extension
+    async fn prepare(&mut self) -> error::Result<()> {
+        if self.poisoned {
+            self.recover().await?;
+        }
+        let limits = self.limits.clone();
+        reset_limits(self.store_mut(), &limits)
+    }
+
+    /// Replace the poisoned instance with a new one and run the last setup again
+    ///
+    /// The instance gets a new store holding the state of the old one, so the
+    /// memory of the failed instance is freed rather than kept in the store.
+    async fn recover(&mut self) -> error::Result<()> {
+        log::warn!("extension failed, creating a new instance");
+        let state = self.store.take().expect(STORE_TAKEN).into_data();
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limiter);
+        reset_limits(&mut store, &self.limits)?;
+
+        let (instance, exports) = match instantiate(&self.linker, &self.component, &mut store).await
+        {
+            Ok(instance) => instance,
+            Err(error) => {
+                // Keep the store, so the next call can try to recover again
+                self.store = Some(store);
+                return Err(error);
+            }
+        };
+        self.store = Some(store);
+        self.instance = instance;
+        self.exports = exports;
+        self.poisoned = false;
+
+        if let Some(level_filter) = self.setup_level {
+            self.call_setup(level_filter).await?;
+        }
+
+        self.apply_level_filter().await
+    }
+
+    fn store(&self) -> &Store<State<D>> {
+        self.store.as_ref().expect(STORE_TAKEN)
+    }
+
+    fn store_mut(&mut self) -> &mut Store<State<D>> {
+        self.store.as_mut().expect(STORE_TAKEN)
+    }
+
+    /// Mark the instance as poisoned if the call failed or ran past the deadline
+    ///
+    /// Unlike a module, a component instance refuses to be entered again after
+    /// any failed call, including one failed by a host function, so every
+    /// failure poisons it. Errors returned by the extension are not failures.
+    fn check<T>(&mut self, result: Option<wasmtime::Result<T>>) -> error::Result<T> {
+        let Some(result) = result else {
+            self.poisoned = true;
+            return Err(Error::LimitExceeded(Limit::Deadline));
+        };
+
+        result.map_err(|error| {
+            self.poisoned = true;
+            Error::from(error)
+        })
+    }
+}
+
+fn returned<T, U: Into<T>>(result: Result<U, types::QuelleError>) -> error::Result<T> {
+    result
+        .map(Into::into)
+        .map_err(|e| Error::ReturnedError(e.into()))
+}
+
+fn novels(
+    result: Result<Vec<BasicNovel>, types::QuelleError>,
+) -> error::Result<Vec<CoreBasicNovel>> {
+    returned::<Vec<BasicNovel>, _>(result)
+        .map(|novels| novels.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use wit_component::{ComponentEncoder, StringEncoding};
+    use wit_parser::Resolve;
+
+    use super::*;
+    use crate::{data::DefaultImpl, Runtime};
+
+    /// Encode the core module of the fixture into a component of the world
+    fn component(name: &str, world: &str) -> tempfile::NamedTempFile {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+        let mut resolve = Resolve::default();
+        let (package, _) = resolve.push_dir(&root.join("../../wit")).unwrap();
+        let world = resolve.select_world(package, Some(world)).unwrap();
+
+        let mut module = wat::parse_file(root.join("tests/fixtures").join(name)).unwrap();
+        wit_component::embed_component_metadata(&mut module, &resolve, world, StringEncoding::UTF8)
+            .unwrap();
+
+        let component = ComponentEncoder::default()
+            .module(&module)
+            .unwrap()
+            .validate(true)
+            .encode()
+            .unwrap();
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&component).unwrap();
+        file
+    }
+
+    #[test]
+    fn should_tell_components_from_modules() {
+        assert!(is_component(b"\0asm\x0d\0\x01\0"));
+        assert!(is_component(b"  (component)"));
+        assert!(!is_component(b"\0asm\x01\0\0\0"));
+        assert!(!is_component(b"(module)"));
+    }
+
+    #[tokio::test]
+    async fn should_require_the_source_interface() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"(component)").unwrap();
+
+        let runtime = Runtime::<DefaultImpl>::builder()
+            .build_component(file.path(), DefaultImpl::default())
+            .await;
+        assert!(matches!(runtime, Err(Error::MissingExport(name)) if name == SOURCE));
+    }
+
+    #[tokio::test]
+    async fn should_call_the_source_interface() {
+        let file = component("source.wat", "source-extension");
+        let mut runtime = Runtime::<DefaultImpl>::builder()
+            .build_component(file.path(), DefaultImpl::default())
+            .await
+            .unwrap();
+
+        assert!(!runtime.popular_supported());
+        assert!(matches!(
+            runtime.popular(1).await,
+            Err(Error::NotSupported(AffectedFunction::Popular))
+        ));
+
+        runtime.setup(&ExtensionConfig::default()).await.unwrap();
+        let meta = runtime.meta().await.unwrap();
+        assert_eq!(meta.id, "en.test");
+        assert_eq!(meta.base_urls, ["https://test.example.com"]);
+        assert!(meta.rate_limit.is_none());
+
+        let content = runtime
+            .fetch_chapter_content("https://test.example.com/1")
+            .await;
+        assert!(matches!(
+            content,
+            Err(Error::ReturnedError(QuelleError::Utf8Error))
+        ));
+        assert!(!runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_recover_from_a_trap() {
+        let file = component("source.wat", "source-extension");
+        let mut runtime = Runtime::<DefaultImpl>::builder()
+            .build_component(file.path(), DefaultImpl::default())
+            .await
+            .unwrap();
+
+        runtime.setup(&ExtensionConfig::default()).await.unwrap();
+        for _ in 0..3 {
+            let novel = runtime.fetch_novel("https://test.example.com/novel").await;
+            assert!(novel.is_err());
+            assert!(runtime.is_poisoned());
+        }
+
+        assert_eq!(runtime.meta().await.unwrap().id, "en.test");
+        assert!(!runtime.is_poisoned());
+    }
+}
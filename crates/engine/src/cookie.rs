@@ -0,0 +1,499 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// A cookie set by a website the extension sent a request to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Only sent to `domain` itself and not to its subdomains
+    pub host_only: bool,
+    pub path: String,
+    pub secure: bool,
+    /// The expiry as seconds since the unix epoch, or `None` for a session cookie
+    pub expires: Option<u64>,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` header received from the url
+    pub fn parse(header: &str, url: &Url) -> Option<Cookie> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let mut parts = header.split(';');
+
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            secure: false,
+            expires: None,
+        };
+
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                "expires" => {
+                    if let Ok(time) = httpdate::parse_http_date(value) {
+                        cookie.expires = Some(unix_seconds(time));
+                    }
+                }
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                _ => {}
+            }
+        }
+
+        // Max-Age takes precedence over Expires
+        if let Some(max_age) = max_age {
+            let now = unix_seconds(SystemTime::now());
+            cookie.expires = Some(now.saturating_add_signed(max_age.max(-1)));
+        }
+
+        Some(cookie)
+    }
+
+    /// Whether the cookie should be sent with a request to the url
+    pub fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+
+        let host_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+
+        host_matches
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| expires <= unix_seconds(SystemTime::now()))
+    }
+}
+
+/// The cookies of a single extension
+///
+/// Clones share the same cookies. A jar opened from a [CookieStore] with a
+/// directory writes itself back to disk whenever a response changes it.
+#[derive(Clone, Default)]
+pub struct CookieJar {
+    inner: Arc<Mutex<JarInner>>,
+}
+
+#[derive(Default)]
+struct JarInner {
+    cookies: Vec<Cookie>,
+    path: Option<PathBuf>,
+}
+
+impl CookieJar {
+    /// The value of the `Cookie` header for a request to the url
+    pub fn header(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let inner = self.inner.lock().unwrap();
+
+        let header = inner
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired() && cookie.matches(&url))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        (!header.is_empty()).then_some(header)
+    }
+
+    /// Store the cookies from the `Set-Cookie` headers of a response from the url
+    pub fn store(&self, url: &str, headers: &[String]) {
+        let Ok(url) = Url::parse(url) else {
+            return;
+        };
+
+        let cookies = headers
+            .iter()
+            .filter_map(|header| Cookie::parse(header, &url))
+            .collect::<Vec<_>>();
+
+        if cookies.is_empty() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.merge(cookies);
+        inner.save();
+    }
+
+    /// Every cookie that has not expired
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Remove every cookie, including the saved ones
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.cookies.clear();
+        inner.save();
+    }
+
+    fn open(path: Option<PathBuf>) -> Self {
+        let cookies = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(
+                |bytes| match serde_json::from_slice::<Vec<Cookie>>(&bytes) {
+                    Ok(cookies) => Some(cookies),
+                    Err(e) => {
+                        warn!("ignored unreadable cookie jar: {e}");
+                        None
+                    }
+                },
+            )
+            .unwrap_or_default();
+
+        Self {
+            inner: Arc::new(Mutex::new(JarInner { cookies, path })),
+        }
+    }
+
+    /// Move the cookies of this jar into another one
+    fn merge_into(&self, other: &CookieJar) {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return;
+        }
+
+        let cookies = std::mem::take(&mut self.inner.lock().unwrap().cookies);
+        let mut inner = other.inner.lock().unwrap();
+        inner.merge(cookies);
+        inner.save();
+    }
+
+    /// Save the jar to the path, keeping the cookies already saved there
+    fn attach(&self, path: PathBuf) {
+        let saved = CookieJar::open(Some(path.clone())).cookies();
+
+        let mut inner = self.inner.lock().unwrap();
+        let cookies = std::mem::replace(&mut inner.cookies, saved);
+        inner.merge(cookies);
+        inner.path = Some(path);
+        inner.save();
+    }
+}
+
+impl JarInner {
+    /// Replace the cookies with the same name, domain and path
+    fn merge(&mut self, cookies: Vec<Cookie>) {
+        for cookie in cookies {
+            self.cookies.retain(|c| {
+                (&c.name, &c.domain, &c.path) != (&cookie.name, &cookie.domain, &cookie.path)
+            });
+            if !cookie.is_expired() {
+                self.cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Write the session-independent cookies to disk
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let cookies = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.expires.is_some() && !cookie.is_expired())
+            .collect::<Vec<_>>();
+
+        // Write to a temporary file first, so a partial write never replaces the jar
+        let tmp = path.with_extension("json.tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp, serde_json::to_vec(&cookies).unwrap_or_default()))
+            .and_then(|_| fs::rename(&tmp, path));
+
+        if let Err(e) = result {
+            warn!("failed to save cookie jar to '{}': {e}", path.display());
+        }
+    }
+}
+
+/// The cookie jars of every extension, keyed by the extension id
+///
+/// With a directory, each jar is saved to `<dir>/<id>.json`. The jars of ids that
+/// are not valid file names are only kept in memory.
+#[derive(Clone, Default)]
+pub struct CookieStore {
+    dir: Option<PathBuf>,
+    jars: Arc<Mutex<HashMap<String, CookieJar>>>,
+}
+
+impl CookieStore {
+    /// Keep the cookies in memory only
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Save the cookies to the directory so they outlive the process
+    pub fn persistent(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            ..Default::default()
+        }
+    }
+
+    /// The jar of the extension with the id
+    pub fn jar(&self, id: &str) -> CookieJar {
+        let mut jars = self.jars.lock().unwrap();
+        let jar = jars.entry(id.to_string()).or_insert_with(|| {
+            CookieJar::open(self.dir.as_ref().and_then(|dir| extension_file(dir, id)))
+        });
+
+        jar.clone()
+    }
+
+    /// Make the jar the one of the extension with the id
+    ///
+    /// The cookies of the jar are moved into the jar already known for the id,
+    /// which is returned. Otherwise the jar itself is used for the id from now on.
+    pub(crate) fn bind(&self, id: &str, jar: &CookieJar) -> CookieJar {
+        let mut jars = self.jars.lock().unwrap();
+        match jars.get(id) {
+            Some(existing) => {
+                jar.merge_into(existing);
+                existing.clone()
+            }
+            None => {
+                if let Some(path) = self.dir.as_ref().and_then(|dir| extension_file(dir, id)) {
+                    jar.attach(path);
+                }
+                jars.insert(id.to_string(), jar.clone());
+                jar.clone()
+            }
+        }
+    }
+}
+
+/// The `<dir>/<id>.json` file of the extension, if the id is a valid file name
+///
+/// Ids are only allowed ascii letters, digits, `.`, `_` and `-`, so an extension
+/// can not name a file outside of the directory.
+pub(crate) fn extension_file(dir: &Path, id: &str) -> Option<PathBuf> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+
+    if !valid {
+        warn!("the extension id '{id}' is not a valid file name, keeping its data in memory");
+        return None;
+    }
+
+    Some(dir.join(format!("{id}.json")))
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(index) => url.path()[..index].to_string(),
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || path
+            .strip_prefix(cookie_path)
+            .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn should_parse_set_cookie() {
+        let cookie = Cookie::parse(
+            "token=abc; Domain=.example.com; Path=/novel; Secure; HttpOnly",
+            &url("https://www.example.com/novel/1"),
+        )
+        .unwrap();
+
+        assert_eq!(cookie.name, "token");
+        assert_eq!(cookie.value, "abc");
+        assert_eq!(cookie.domain, "example.com");
+        assert!(!cookie.host_only);
+        assert_eq!(cookie.path, "/novel");
+        assert!(cookie.secure);
+        assert_eq!(cookie.expires, None);
+    }
+
+    #[test]
+    fn should_reject_cookie_for_other_domain() {
+        let cookie = Cookie::parse("a=1; Domain=other.com", &url("https://example.com"));
+        assert!(cookie.is_none());
+    }
+
+    #[test]
+    fn should_send_matching_cookies() {
+        let jar = CookieJar::default();
+        jar.store(
+            "https://www.example.com/novel/1",
+            &[
+                String::from("session=1"),
+                String::from("token=2; Domain=example.com; Path=/"),
+                String::from("secure=3; Path=/; Secure"),
+            ],
+        );
+
+        assert_eq!(
+            jar.header("https://www.example.com/novel/2").as_deref(),
+            Some("session=1; token=2; secure=3")
+        );
+        assert_eq!(
+            jar.header("http://cdn.example.com/").as_deref(),
+            Some("token=2")
+        );
+        assert_eq!(jar.header("https://example.org/"), None);
+    }
+
+    #[test]
+    fn should_remove_expired_cookies() {
+        let jar = CookieJar::default();
+        jar.store(
+            "https://example.com",
+            &[String::from("a=1"), String::from("b=2")],
+        );
+        jar.store("https://example.com", &[String::from("a=; Max-Age=0")]);
+
+        let names = jar
+            .cookies()
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["b"]);
+    }
+
+    #[test]
+    fn should_persist_cookies_per_extension() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = CookieStore::persistent(dir.path().to_path_buf());
+        store.jar("en.example").store(
+            "https://example.com",
+            &[
+                String::from("session=1"),
+                String::from("token=2; Max-Age=3600"),
+            ],
+        );
+        assert!(dir.path().join("en.example.json").exists());
+        assert!(!dir.path().join("en.example.json.tmp").exists());
+
+        let store = CookieStore::persistent(dir.path().to_path_buf());
+        let cookies = store.jar("en.example").cookies();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name, "token");
+        assert!(store.jar("en.other").cookies().is_empty());
+
+        store.jar("en.example").clear();
+        let store = CookieStore::persistent(dir.path().to_path_buf());
+        assert!(store.jar("en.example").cookies().is_empty());
+    }
+
+    #[test]
+    fn should_keep_jars_of_invalid_ids_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CookieStore::persistent(dir.path().join("cookies"));
+
+        for id in ["../escape", "en/example", "..", ""] {
+            assert_eq!(extension_file(dir.path(), id), None);
+            store
+                .jar(id)
+                .store("https://example.com", &[String::from("a=1; Max-Age=3600")]);
+            assert_eq!(store.jar(id).cookies().len(), 1);
+        }
+
+        assert!(!dir.path().join("escape.json").exists());
+        assert!(!dir.path().join("cookies").exists());
+    }
+
+    #[test]
+    fn should_bind_jar_to_extension_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CookieStore::persistent(dir.path().to_path_buf());
+
+        let jar = CookieJar::default();
+        jar.store("https://example.com", &[String::from("a=1; Max-Age=3600")]);
+        let bound = store.bind("en.example", &jar);
+        assert!(Arc::ptr_eq(&bound.inner, &jar.inner));
+
+        let other = CookieJar::default();
+        other.store("https://example.com", &[String::from("b=2")]);
+        let bound = store.bind("en.example", &other);
+        assert!(Arc::ptr_eq(&bound.inner, &jar.inner));
+        assert_eq!(
+            jar.header("https://example.com").as_deref(),
+            Some("a=1; b=2")
+        );
+
+        let store = CookieStore::persistent(dir.path().to_path_buf());
+        assert_eq!(store.jar("en.example").cookies().len(), 1);
+    }
+}
@@ -1,3 +1,145 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use log::LevelFilter;
+
+use crate::{
+    client::{ConfiguredClient, HttpClient, HttpConfig},
+    clock::Clock,
+    cookie::CookieJar,
+    error,
+    limits::MemoryLimiter,
+    mirror::Mirrors,
+    network::NetworkPolicy,
+    output::Output,
+    rate_limit::RateLimiter,
+    replay::Fixtures,
+    retry::RetryPolicy,
+    storage::Storage,
+};
+
+#[derive(Clone)]
 pub struct DefaultImpl {
-    pub client: reqwest::Client,
+    pub client: ConfiguredClient,
+}
+
+impl DefaultImpl {
+    pub fn new(http: &HttpConfig) -> error::Result<Self> {
+        Ok(Self {
+            client: http.client()?,
+        })
+    }
+}
+
+impl Default for DefaultImpl {
+    fn default() -> Self {
+        Self::new(&HttpConfig::default()).unwrap()
+    }
+}
+
+/// The data held by the wasm store
+///
+/// Derefs to the data given to [RuntimeBuilder::build](crate::RuntimeBuilder::build),
+/// so host functions can use it through [Caller::data](wasmtime::Caller::data).
+pub struct State<D> {
+    pub(crate) data: D,
+    pub(crate) limiter: MemoryLimiter,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) cookie_jar: CookieJar,
+    pub(crate) network: NetworkPolicy,
+    pub(crate) mirrors: Mirrors,
+    pub(crate) http_client: Arc<dyn HttpClient>,
+    pub(crate) fixtures: Option<Fixtures>,
+    pub(crate) output: Output,
+    pub(crate) clock: Clock,
+    pub(crate) storage: Storage,
+    /// The number of requests of a batch sent at once
+    pub(crate) max_concurrent_requests: usize,
+    /// The ABI version of the extension, which decides the layout of its requests
+    pub(crate) abi_version: u32,
+    /// The id of the extension once its meta was read
+    pub(crate) extension_id: Option<String>,
+    /// The level of the extension logs forwarded to the host when changed after setup
+    pub(crate) level_filter: Option<LevelFilter>,
+}
+
+impl<D> State<D> {
+    /// The schedule every request of the extension has to go through
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// How failed requests of the extension are retried
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// The cookies shared by the requests of the extension
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookie_jar
+    }
+
+    /// The rules every request of the extension has to follow
+    pub fn network(&self) -> &NetworkPolicy {
+        &self.network
+    }
+
+    /// The equivalent base urls the requests of the extension are moved between
+    pub fn mirrors(&self) -> &Mirrors {
+        &self.mirrors
+    }
+
+    /// What sends the requests of the extension
+    pub fn http_client(&self) -> &dyn HttpClient {
+        self.http_client.as_ref()
+    }
+
+    /// The recorded requests the extension is answered from, if any
+    pub fn fixtures(&self) -> Option<&Fixtures> {
+        self.fixtures.as_ref()
+    }
+
+    /// Where the text printed by the extension goes
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// The time and timezone handed to the extension
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// The values the extension keeps between calls and runs
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// The number of requests of a batch the extension may have in flight at once
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.max_concurrent_requests
+    }
+
+    /// The id of the extension, known once its meta was read
+    pub fn extension_id(&self) -> Option<&str> {
+        self.extension_id.as_deref()
+    }
+}
+
+impl<D> Deref for State<D> {
+    type Target = D;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<D> DerefMut for State<D> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
 }
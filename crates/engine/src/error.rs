@@ -1,8 +1,10 @@
-use std::{fmt::Display, string::FromUtf8Error};
+use std::{fmt::Display, str::Utf8Error, string::FromUtf8Error};
 
 use quelle_core::prelude::QuelleError;
 use wasmtime::Trap;
 
+use crate::{capabilities::SUPPORTED_ABI_VERSIONS, limits::Limit};
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
@@ -26,28 +28,89 @@ pub enum Error {
     FailedResultAttempt,
 
     #[error("{0}")]
-    Utf8Error(#[from] FromUtf8Error),
+    Utf8Error(#[from] Utf8Error),
 
     #[error("wasm memory access error")]
     MemoryAccessError,
 
+    #[error("extension does not export '{0}'")]
+    MissingExport(String),
+
+    #[error(
+        "extension was built for ABI version {0}, but the engine supports versions {} to {}",
+        SUPPORTED_ABI_VERSIONS.start(),
+        SUPPORTED_ABI_VERSIONS.end()
+    )]
+    UnsupportedAbi(u32),
+
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("extension exceeded the {0} limit")]
+    LimitExceeded(Limit),
+
+    #[error("invalid http config: {0}")]
+    InvalidHttpConfig(String),
+
+    #[error("no fixture recorded for {0}")]
+    MissingFixture(String),
+
     #[error("{0}")]
-    Other(#[from] anyhow::Error),
+    Other(anyhow::Error),
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(error: FromUtf8Error) -> Self {
+        Error::Utf8Error(error.utf8_error())
+    }
 }
 
-#[derive(Debug)]
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+
+        match error.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => Error::LimitExceeded(Limit::Fuel),
+            Some(Trap::Interrupt) => Error::LimitExceeded(Limit::Deadline),
+            _ => Error::Other(error),
+        }
+    }
+}
+
+/// An optional export of the extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AffectedFunction {
-    Search,
+    PopularUrl,
     Popular,
+    TextSearchUrl,
+    TextSearch,
+    FilterOptions,
+    FilterSearchUrl,
+    FilterSearch,
+    ImageRequest,
 }
 
-impl Display for AffectedFunction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let value = match self {
-            AffectedFunction::Search => "search",
+impl AffectedFunction {
+    /// The name the function is exported by
+    pub fn export_name(&self) -> &'static str {
+        match self {
+            AffectedFunction::PopularUrl => "popular_url",
             AffectedFunction::Popular => "popular",
-        };
+            AffectedFunction::TextSearchUrl => "text_search_url",
+            AffectedFunction::TextSearch => "text_search",
+            AffectedFunction::FilterOptions => "filter_options",
+            AffectedFunction::FilterSearchUrl => "filter_search_url",
+            AffectedFunction::FilterSearch => "filter_search",
+            AffectedFunction::ImageRequest => "image_request",
+        }
+    }
+}
 
-        write!(f, "{value}")
+impl Display for AffectedFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}'", self.export_name())
     }
 }
@@ -1,36 +1,99 @@
+pub mod any;
+pub mod cache;
+pub mod capabilities;
+pub mod client;
+pub mod clock;
+pub mod component;
+pub mod cookie;
 pub mod data;
 pub mod error;
+pub mod limits;
+pub mod mirror;
 pub mod module;
-
-use data::DefaultImpl;
+pub mod network;
+pub mod output;
+pub mod pool;
+pub mod rate_limit;
+pub mod replay;
+pub mod retry;
+pub mod storage;
+
+use any::AnyRuntime;
+use cache::ModuleCache;
+use capabilities::{Capabilities, Trait, SUPPORTED_ABI_VERSIONS};
+use client::{HttpClient, HttpConfig};
+use clock::Clock;
+use component::ComponentRuntime;
+use cookie::{CookieJar, CookieStore};
+use data::{DefaultImpl, State};
 use error::Error;
-use quelle_core::prelude::*;
-use serde::{de::DeserializeOwned, Serialize};
-use std::{future::Future, path::Path, slice};
+use limits::{Limit, Limits, MemoryLimiter};
+use log::LevelFilter;
+use mirror::Mirrors;
+use network::NetworkPolicy;
+use output::Output;
+use pool::RuntimePool;
+use quelle_core::{abi::ABI_VERSION, prelude::*};
+use rate_limit::RateLimiter;
+use replay::Fixtures;
+use retry::RetryPolicy;
+use serde::de::DeserializeOwned;
+use std::{path::Path, sync::Arc, time::Duration};
+use storage::Storage;
 use wasmtime::*;
 
-type SendRequestFn<D> =
-    fn(caller: Caller<'_, D>, ptr: i32, len: i32) -> Box<dyn Future<Output = i32> + Send + '_>;
-
-type LogFn<D> = fn(caller: Caller<'_, D>, ptr: i32, len: i32);
+type LogFn<D> = fn(caller: Caller<'_, State<D>>, ptr: i32, len: i32);
 
 pub struct RuntimeBuilder<D> {
-    send_request: Option<SendRequestFn<D>>,
+    http_client: Option<Arc<dyn HttpClient>>,
     log: Option<LogFn<D>>,
+    limits: Limits,
+    module_cache: Option<ModuleCache>,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    cookie_store: CookieStore,
+    network: NetworkPolicy,
+    mirrors: Mirrors,
+    fixtures: Option<Fixtures>,
+    output: Output,
+    clock: Clock,
+    storage: Storage,
+    max_concurrent_requests: usize,
 }
 
 impl<D> Default for RuntimeBuilder<D> {
     fn default() -> Self {
         Self {
-            send_request: Default::default(),
+            http_client: Default::default(),
             log: Default::default(),
+            limits: Default::default(),
+            module_cache: Default::default(),
+            rate_limiter: Default::default(),
+            retry_policy: Default::default(),
+            cookie_store: Default::default(),
+            network: Default::default(),
+            mirrors: Default::default(),
+            fixtures: Default::default(),
+            output: Default::default(),
+            clock: Default::default(),
+            storage: Default::default(),
+            max_concurrent_requests: 4,
         }
     }
 }
 
-impl<D: Send + 'static> RuntimeBuilder<D> {
-    pub fn send_request(mut self, f: SendRequestFn<D>) -> Self {
-        self.send_request = Some(f);
+impl<D: Send + Sync + 'static> RuntimeBuilder<D> {
+    /// Send the requests of the extension with the client
+    ///
+    /// Without one, every request of the extension fails.
+    pub fn http_client<C: HttpClient + 'static>(mut self, client: C) -> Self {
+        self.http_client = Some(Arc::new(client));
+        self
+    }
+
+    /// Send at most `n` requests of a batch at once
+    pub fn max_concurrent_requests(mut self, n: usize) -> Self {
+        self.max_concurrent_requests = n.max(1);
         self
     }
 
@@ -39,17 +102,218 @@ impl<D: Send + 'static> RuntimeBuilder<D> {
         self
     }
 
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Fail a call that takes longer than the deadline
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.limits.deadline = Some(deadline);
+        self
+    }
+
+    /// Fail a call that consumes more than the given fuel
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.limits.fuel = Some(fuel);
+        self
+    }
+
+    /// Fail a call that grows the linear memory past the given size in bytes
+    pub fn max_memory(mut self, max_memory: usize) -> Self {
+        self.limits.max_memory = Some(max_memory);
+        self
+    }
+
+    /// Load compiled modules from the cache instead of compiling on every build
+    pub fn module_cache(mut self, cache: ModuleCache) -> Self {
+        self.module_cache = Some(cache);
+        self
+    }
+
+    /// Schedule the requests of the extension with the rate limiter
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Retry requests that failed with a transient error according to the policy
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Keep the cookies of the extension in the store once its id is known
+    pub fn cookie_store(mut self, cookie_store: CookieStore) -> Self {
+        self.cookie_store = cookie_store;
+        self
+    }
+
+    /// Send the text the extension prints to stdout and stderr to the output
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Hand the time and timezone of the clock to the extension
+    pub fn clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keep the values the extension stores in the storage once its id is known
+    pub fn storage(mut self, storage: Storage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Restrict the hosts the extension can reach and the responses it receives
+    pub fn network(mut self, network: NetworkPolicy) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Move the requests of the extension between the mirrors of its base urls
+    ///
+    /// Share the mirrors with the client to learn which one is used.
+    pub fn mirrors(mut self, mirrors: Mirrors) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Record the requests of the extension to the fixtures or replay them from it
+    ///
+    /// Recorded requests are sent with the [HttpClient] of the builder, while
+    /// replayed ones never reach it.
+    pub fn fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
     pub async fn build(self, path: &Path, data: D) -> error::Result<Runtime<D>> {
+        let engine = self.engine()?;
+        let module = self.module(&engine, path)?;
+        let linker = self.linker(&engine)?;
+
+        let state = self.state(data, CookieJar::default());
+        Runtime::with_module(
+            engine,
+            module,
+            linker,
+            self.limits,
+            self.cookie_store,
+            state,
+        )
+        .await
+    }
+
+    /// Build a runtime for an extension built as a component of the `extension` world
+    ///
+    /// Use [component::is_component] to find out which kind of extension a file holds.
+    /// The `log` implementation of the builder only applies to modules.
+    pub async fn build_component(self, path: &Path, data: D) -> error::Result<ComponentRuntime<D>> {
+        let engine = self.engine()?;
+        let state = self.state(data, CookieJar::default());
+        ComponentRuntime::new(&engine, path, self.limits, self.cookie_store, state).await
+    }
+
+    /// Build a runtime for an extension of either kind, see [component::is_component]
+    pub async fn build_any(self, path: &Path, data: D) -> error::Result<AnyRuntime<D>> {
+        let bytes = std::fs::read(path)?;
+        if component::is_component(&bytes) {
+            Ok(self.build_component(path, data).await?.into())
+        } else {
+            Ok(self.build(path, data).await?.into())
+        }
+    }
+
+    /// Build a pool of `size` runtimes that share one compiled module
+    pub async fn build_pool(
+        self,
+        path: &Path,
+        size: usize,
+        data: D,
+    ) -> error::Result<RuntimePool<D>>
+    where
+        D: Clone,
+    {
+        let engine = self.engine()?;
+        let module = self.module(&engine, path)?;
+        let linker = self.linker(&engine)?;
+
+        // The runtimes are instances of the same extension
+        let cookie_jar = CookieJar::default();
+
+        let mut runtimes = Vec::with_capacity(size);
+        for _ in 0..size {
+            let runtime = Runtime::with_module(
+                engine.clone(),
+                module.clone(),
+                linker.clone(),
+                self.limits.clone(),
+                self.cookie_store.clone(),
+                self.state(data.clone(), cookie_jar.clone()),
+            )
+            .await?;
+            runtimes.push(runtime);
+        }
+
+        Ok(RuntimePool::from_runtimes(runtimes))
+    }
+
+    fn state(&self, data: D, cookie_jar: CookieJar) -> State<D> {
+        State {
+            data,
+            limiter: MemoryLimiter {
+                max_memory: self.limits.max_memory,
+            },
+            rate_limiter: self.rate_limiter.clone(),
+            retry_policy: self.retry_policy.clone(),
+            cookie_jar,
+            network: self.network.clone(),
+            mirrors: self.mirrors.clone(),
+            http_client: self
+                .http_client
+                .clone()
+                .unwrap_or_else(|| Arc::new(client::Unsupported)),
+            fixtures: self.fixtures.clone(),
+            output: self.output.clone(),
+            clock: self.clock.clone(),
+            storage: self.storage.clone(),
+            max_concurrent_requests: self.max_concurrent_requests,
+            abi_version: ABI_VERSION,
+            extension_id: None,
+            level_filter: None,
+        }
+    }
+
+    fn engine(&self) -> error::Result<Engine> {
         let mut config = Config::new();
         config.async_support(true);
-        // config.consume_fuel(true);
+        config.wasm_component_model(true);
+        config.consume_fuel(self.limits.fuel.is_some());
+        config.epoch_interruption(self.limits.deadline.is_some());
 
         let engine = Engine::new(&config)?;
-        let mut linker: Linker<D> = Linker::new(&engine);
-        let module = Module::from_file(&engine, path)?;
+        if self.limits.deadline.is_some() {
+            limits::spawn_epoch_ticker(&engine);
+        }
 
-        let send_request = self.send_request.unwrap_or(module::http::send_request_noop);
-        linker.func_wrap2_async("env", "http_send_request", send_request)?;
+        Ok(engine)
+    }
+
+    fn module(&self, engine: &Engine, path: &Path) -> error::Result<Module> {
+        match &self.module_cache {
+            Some(cache) => cache.load(engine, path),
+            None => Ok(Module::from_file(engine, path)?),
+        }
+    }
+
+    fn linker(&self, engine: &Engine) -> error::Result<Linker<State<D>>> {
+        let mut linker: Linker<State<D>> = Linker::new(engine);
+
+        linker.func_wrap2_async("env", "http_send_request", module::http::send_request)?;
+        linker.func_wrap2_async("env", "http_send_requests", module::http::send_requests)?;
 
         let log_event = self.log.unwrap_or(module::log::event);
         linker.func_wrap("env", "log_event", log_event)?;
@@ -58,70 +322,116 @@ impl<D: Send + 'static> RuntimeBuilder<D> {
         linker.func_wrap("env", "io_eprint", module::io::eprint)?;
         linker.func_wrap("env", "io_trace", module::io::trace)?;
 
-        let mut store = Store::new(&engine, data);
+        linker.func_wrap("env", "time_now", module::time::now)?;
+        linker.func_wrap("env", "time_utc_offset", module::time::utc_offset)?;
 
-        let instance = linker.instantiate_async(&mut store, &module).await?;
-        let memory = instance
-            .get_memory(&mut store, "memory")
-            .ok_or(anyhow::format_err!("failed to find `memory` export"))?;
+        linker.func_wrap2_async("env", "storage_get", module::storage::get)?;
+        linker.func_wrap2_async("env", "storage_set", module::storage::set)?;
+        linker.func_wrap2_async("env", "storage_delete", module::storage::delete)?;
 
-        macro_rules! get_func {
-            ($name:literal) => {
-                instance
-                    .get_func(&mut store, $name)
-                    .expect(r#"$name is not an exported function"#)
-                    .typed(&store)?
-            };
-        }
+        Ok(linker)
+    }
+}
 
-        macro_rules! get_func_optional {
-            ($name:literal) => {
-                instance
-                    .get_func(&mut store, $name)
-                    .map(|f| f.typed(&store))
-                    .transpose()?
-            };
+/// Create a new instance of the module along with its memory and exports
+async fn instantiate<D: Send>(
+    linker: &Linker<State<D>>,
+    module: &Module,
+    store: &mut Store<State<D>>,
+) -> error::Result<(Instance, Memory, Functions)> {
+    let instance = linker.instantiate_async(&mut *store, module).await?;
+
+    // Check the version before the exports, which may differ between versions
+    let abi_version = match instance.get_func(&mut *store, "abi_version") {
+        Some(func) => {
+            let func = func.typed::<(), i32>(&*store)?;
+            func.call_async(&mut *store, ()).await? as u32
         }
+        None => 0,
+    };
+    if !SUPPORTED_ABI_VERSIONS.contains(&abi_version) {
+        return Err(Error::UnsupportedAbi(abi_version));
+    }
+    store.data_mut().abi_version = abi_version;
+
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| Error::MissingExport(String::from("memory")))?;
+
+    macro_rules! get_func {
+        ($name:literal) => {
+            instance
+                .get_func(&mut *store, $name)
+                .ok_or_else(|| Error::MissingExport(String::from($name)))?
+                .typed(&*store)?
+        };
+    }
 
-        let functions = Functions {
-            alloc: get_func!("alloc"),
-            dealloc: get_func!("dealloc"),
-            stack_push: get_func!("stack_push"),
-            stack_pop: get_func!("stack_pop"),
-            last_result: get_func!("last_result"),
-            setup: get_func_optional!("setup"),
-            setup_default: get_func!("setup_default"),
-            meta: get_func!("meta"),
-            fetch_novel: get_func!("fetch_novel"),
-            fetch_chapter_content: get_func!("fetch_chapter_content"),
-            popular_url: get_func_optional!("popular_url"),
-            popular: get_func_optional!("popular"),
-            text_search_url: get_func_optional!("text_search_url"),
-            text_search: get_func_optional!("text_search"),
-            filter_options: get_func_optional!("filter_options"),
-            filter_search_url: get_func_optional!("filter_search_url"),
-            filter_search: get_func_optional!("filter_search"),
+    macro_rules! get_func_optional {
+        ($name:literal) => {
+            instance
+                .get_func(&mut *store, $name)
+                .map(|f| f.typed(&*store))
+                .transpose()?
         };
+    }
 
-        Ok(Runtime {
-            engine,
-            module,
-            store,
-            instance,
-            memory,
-            functions,
-        })
+    let functions = Functions {
+        alloc: get_func!("alloc"),
+        dealloc: get_func!("dealloc"),
+        stack_push: get_func!("stack_push"),
+        stack_pop: get_func!("stack_pop"),
+        last_result: get_func!("last_result"),
+        abi_version,
+        setup: get_func_optional!("setup"),
+        setup_default: get_func!("setup_default"),
+        set_level_filter: get_func_optional!("set_level_filter"),
+        meta: get_func!("meta"),
+        fetch_novel: get_func!("fetch_novel"),
+        fetch_chapter_content: get_func!("fetch_chapter_content"),
+        popular_url: get_func_optional!("popular_url"),
+        popular: get_func_optional!("popular"),
+        text_search_url: get_func_optional!("text_search_url"),
+        text_search: get_func_optional!("text_search"),
+        filter_options: get_func_optional!("filter_options"),
+        filter_search_url: get_func_optional!("filter_search_url"),
+        filter_search: get_func_optional!("filter_search"),
+        image_request: get_func_optional!("image_request"),
+    };
+
+    Ok((instance, memory, functions))
+}
+
+/// Refill the fuel and push back the epoch deadline for the next call
+fn reset_limits<D>(store: &mut Store<D>, limits: &Limits) -> error::Result<()> {
+    if let Some(fuel) = limits.fuel {
+        store.set_fuel(fuel)?;
+    }
+
+    if let Some(ticks) = limits.deadline_ticks() {
+        store.set_epoch_deadline(ticks);
     }
+
+    Ok(())
 }
 
 #[allow(dead_code)]
 pub struct Runtime<D> {
     engine: Engine,
     module: Module,
-    store: Store<D>,
+    linker: Linker<State<D>>,
+    /// Only taken out while a new store is created on [Runtime::recover]
+    store: Option<Store<State<D>>>,
     instance: Instance,
     memory: Memory,
     functions: Functions,
+    limits: Limits,
+    cookie_store: CookieStore,
+    capabilities: Capabilities,
+    /// The serialized config of the last successful setup
+    config: Option<String>,
+    /// Whether the instance trapped and must be replaced before the next call
+    poisoned: bool,
 }
 
 struct Functions {
@@ -137,8 +447,10 @@ struct Functions {
     last_result: TypedFunc<(), i32>,
 
     // Extension
+    abi_version: u32,
     setup: Option<TypedFunc<i32, ()>>,
     setup_default: TypedFunc<i32, ()>,
+    set_level_filter: Option<TypedFunc<i32, ()>>,
 
     meta: TypedFunc<(), i32>,
 
@@ -153,21 +465,25 @@ struct Functions {
     filter_options: Option<TypedFunc<(), i32>>,
     filter_search_url: Option<TypedFunc<(i32, i32), i32>>,
     filter_search: Option<TypedFunc<(i32, i32), i32>>,
+
+    image_request: Option<TypedFunc<i32, i32>>,
 }
 
 impl Runtime<DefaultImpl> {
-    pub async fn new(path: &Path) -> crate::error::Result<Self> {
-        let data = DefaultImpl {
-            client: reqwest::Client::builder()
-                .user_agent("Mozilla/5.0 (X11; Fedora; Linux x86_64; rv:107.0) Gecko/20100101 Firefox/107.0")
-                .build()
-                .unwrap(),
-        };
-
-        RuntimeBuilder::default()
-            .send_request(module::http::send_request)
-            .build(path, data)
-            .await
+    /// Build a runtime with the http config, loading the module from the cache if given
+    pub async fn new(
+        path: &Path,
+        http: &HttpConfig,
+        module_cache: Option<ModuleCache>,
+    ) -> crate::error::Result<Self> {
+        let data = DefaultImpl::new(http)?;
+        let mut builder = RuntimeBuilder::default()
+            .http_client(data.client.clone())
+            .rate_limiter(http.rate_limiter());
+        if let Some(cache) = module_cache {
+            builder = builder.module_cache(cache);
+        }
+        builder.build(path, data).await
     }
 }
 
@@ -180,67 +496,147 @@ where
         RuntimeBuilder::default()
     }
 
+    async fn with_module(
+        engine: Engine,
+        module: Module,
+        linker: Linker<State<D>>,
+        limits: Limits,
+        cookie_store: CookieStore,
+        state: State<D>,
+    ) -> error::Result<Self> {
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limiter);
+        reset_limits(&mut store, &limits)?;
+
+        let (instance, memory, functions) = instantiate(&linker, &module, &mut store).await?;
+        let capabilities = Capabilities {
+            abi_version: functions.abi_version,
+            ..Capabilities::of(&module)
+        };
+
+        Ok(Runtime {
+            engine,
+            module,
+            linker,
+            store: Some(store),
+            instance,
+            memory,
+            functions,
+            limits,
+            cookie_store,
+            capabilities,
+            config: None,
+            poisoned: false,
+        })
+    }
+
+    /// The optional exports, traits and ABI version of the extension
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
     /// Call the extension's setup function
     pub async fn setup(&mut self, config: &ExtensionConfig) -> crate::error::Result<()> {
-        let config = self.write_serialize(config).await?;
+        self.prepare().await?;
+        let config = serde_json::to_string(config).map_err(|_| Error::SerializeError)?;
+        self.call_setup(&config).await?;
+        self.config = Some(config);
+        self.store_mut().data_mut().level_filter = None;
+        Ok(())
+    }
+
+    /// Change the level of the logs the extension sends without running the setup again
+    ///
+    /// Extensions built without support for changing the level keep sending logs
+    /// at the level of their setup, and only the less verbose logs are forwarded.
+    pub async fn set_level_filter(&mut self, level_filter: LevelFilter) -> error::Result<()> {
+        self.prepare().await?;
+        self.store_mut().data_mut().level_filter = Some(level_filter);
+        self.apply_level_filter().await
+    }
+
+    async fn apply_level_filter(&mut self) -> error::Result<()> {
+        let (Some(func), Some(level_filter)) = (
+            self.functions.set_level_filter.clone(),
+            self.store().data().level_filter,
+        ) else {
+            return Ok(());
+        };
 
-        self.functions
+        self.call(func, level_filter as usize as i32).await
+    }
+
+    async fn call_setup(&mut self, config: &str) -> error::Result<()> {
+        let config = self.write_string(config).await?;
+        let setup = self
+            .functions
             .setup
-            .as_ref()
-            .unwrap_or(&self.functions.setup_default)
-            .call_async(&mut self.store, config)
-            .await?;
+            .clone()
+            .unwrap_or_else(|| self.functions.setup_default.clone());
+        self.call(setup, config).await
+    }
 
-        Ok(())
+    /// The cookies of the extension, to inspect or clear them
+    pub fn cookie_jar(&self) -> &CookieJar {
+        self.store().data().cookie_jar()
+    }
+
+    /// Whether the last call trapped, leaving the instance to be replaced on the next call
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
     }
 
+    /// Read the extension's meta
+    ///
+    /// Later requests use the rate limit hinted by the meta and the cookies
+    /// kept for the extension id.
     pub async fn meta(&mut self) -> Result<Meta, crate::error::Error> {
         let memloc = unsafe { self.meta_memloc().await? };
-        let bytes = self.read_bytes_with_len(memloc.offset, memloc.len as usize);
-        let meta = serde_json::from_slice(bytes).map_err(|_| Error::DeserializeError);
+        let bytes = self.read_bytes_with_len(memloc.offset, memloc.len as usize)?;
+        let meta = serde_json::from_slice::<Meta>(bytes).map_err(|_| Error::DeserializeError);
         self.dealloc_memory(memloc.offset, memloc.len).await?;
+
+        if let Ok(meta) = &meta {
+            let state = self.store().data();
+            state.rate_limiter().hint(meta);
+            state.network().hint(meta);
+            state.mirrors().hint(meta);
+
+            let cookie_jar = self.cookie_store.bind(&meta.id, &state.cookie_jar);
+            let state = self.store_mut().data_mut();
+            state.cookie_jar = cookie_jar;
+            state.extension_id = Some(meta.id.clone());
+        }
         meta
     }
 
     pub async unsafe fn meta_memloc(&mut self) -> error::Result<MemLoc> {
-        let offset = self.functions.meta.call_async(&mut self.store, ()).await?;
+        self.prepare().await?;
+        let offset = self.call(self.functions.meta.clone(), ()).await?;
         let len = self.stack_pop().await?;
-        let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-        Ok(MemLoc { offset, ptr, len })
+        self.memloc(offset, len)
     }
 
     pub async fn fetch_novel(&mut self, url: &str) -> crate::error::Result<Novel> {
+        self.prepare().await?;
         let iptr = self.write_string(url).await?;
-        let signed_len = self
-            .functions
-            .fetch_novel
-            .call_async(&mut self.store, iptr)
-            .await?;
+        let signed_len = self.call(self.functions.fetch_novel.clone(), iptr).await?;
         self.parse_result::<Novel, QuelleError>(signed_len).await
     }
 
     pub async unsafe fn fetch_novel_memloc(&mut self, url: &str) -> error::Result<MemLoc> {
+        self.prepare().await?;
         let iptr = self.write_string(url).await?;
-        let len = self
-            .functions
-            .fetch_novel
-            .call_async(&mut self.store, iptr)
-            .await?;
-        let offset = self
-            .functions
-            .last_result
-            .call_async(&mut self.store, ())
-            .await?;
-        let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-        Ok(MemLoc { offset, ptr, len })
+        let len = self.call(self.functions.fetch_novel.clone(), iptr).await?;
+        let offset = self.call(self.functions.last_result.clone(), ()).await?;
+        self.memloc(offset, len)
     }
 
     pub async fn fetch_chapter_content(&mut self, url: &str) -> error::Result<Content> {
+        self.prepare().await?;
         let iptr = self.write_string(url).await?;
         let offset = self
-            .functions
-            .fetch_chapter_content
-            .call_async(&mut self.store, iptr)
+            .call(self.functions.fetch_chapter_content.clone(), iptr)
             .await?;
 
         self.parse_result::<Content, QuelleError>(offset).await
@@ -250,28 +646,23 @@ where
         &mut self,
         url: &str,
     ) -> error::Result<MemLoc> {
+        self.prepare().await?;
         let iptr = self.write_string(url).await?;
         let len = self
-            .functions
-            .fetch_chapter_content
-            .call_async(&mut self.store, iptr)
+            .call(self.functions.fetch_chapter_content.clone(), iptr)
             .await?;
-        let offset = self
-            .functions
-            .last_result
-            .call_async(&mut self.store, ())
-            .await?;
-        let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-        Ok(MemLoc { offset, ptr, len })
+        let offset = self.call(self.functions.last_result.clone(), ()).await?;
+        self.memloc(offset, len)
     }
 
     pub fn popular_supported(&self) -> bool {
-        self.functions.popular.is_some()
+        self.capabilities.supports(Trait::Popular)
     }
 
     pub async fn popular_url(&mut self, page: i32) -> crate::error::Result<String> {
-        if let Some(popular_url) = self.functions.popular_url.as_ref() {
-            let offset = popular_url.call_async(&mut self.store, page).await?;
+        self.prepare().await?;
+        if let Some(popular_url) = self.functions.popular_url.clone() {
+            let offset = self.call(popular_url, page).await?;
             let bytes = self.read_bytes(offset).await?;
             let string = String::from_utf8_lossy(bytes).to_string();
 
@@ -280,47 +671,45 @@ where
 
             Ok(string)
         } else {
-            Err(error::Error::NotSupported(error::AffectedFunction::Popular))
+            Err(error::Error::NotSupported(
+                error::AffectedFunction::PopularUrl,
+            ))
         }
     }
 
     pub async unsafe fn popular_url_memloc(&mut self, page: i32) -> error::Result<MemLoc> {
-        if let Some(popular_url) = self.functions.popular_url.as_ref() {
-            let offset = popular_url.call_async(&mut self.store, page).await?;
+        self.prepare().await?;
+        if let Some(popular_url) = self.functions.popular_url.clone() {
+            let offset = self.call(popular_url, page).await?;
             let len = self.stack_pop().await?;
-            let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-            Ok(MemLoc { offset, ptr, len })
+            self.memloc(offset, len)
         } else {
-            Err(error::Error::NotSupported(error::AffectedFunction::Popular))
+            Err(error::Error::NotSupported(
+                error::AffectedFunction::PopularUrl,
+            ))
         }
     }
 
     async fn call_popular(&mut self, page: i32) -> error::Result<i32> {
-        if let Some(popular) = self.functions.popular.as_ref() {
-            popular
-                .call_async(&mut self.store, page)
-                .await
-                .map_err(|e| e.into())
+        if let Some(popular) = self.functions.popular.clone() {
+            self.call(popular, page).await
         } else {
             Err(error::Error::NotSupported(error::AffectedFunction::Popular))
         }
     }
 
     pub async fn popular(&mut self, page: i32) -> error::Result<Vec<BasicNovel>> {
+        self.prepare().await?;
         let signed_len = self.call_popular(page).await?;
         self.parse_result::<Vec<BasicNovel>, QuelleError>(signed_len)
             .await
     }
 
     pub async unsafe fn popular_memloc(&mut self, page: i32) -> error::Result<MemLoc> {
+        self.prepare().await?;
         let len = self.call_popular(page).await?;
-        let offset = self
-            .functions
-            .last_result
-            .call_async(&mut self.store, ())
-            .await?;
-        let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-        Ok(MemLoc { offset, ptr, len })
+        let offset = self.call(self.functions.last_result.clone(), ()).await?;
+        self.memloc(offset, len)
     }
 
     // --------------------------------------------------------------------------------
@@ -328,22 +717,23 @@ where
     // --------------------------------------------------------------------------------
 
     pub fn text_search_supported(&self) -> bool {
-        self.functions.text_search.is_some()
+        self.capabilities.supports(Trait::TextSearch)
     }
 
     async fn call_text_search_url(&mut self, query: &str, page: i32) -> error::Result<i32> {
         if let Some(text_search) = self.functions.text_search_url.clone() {
             let query_ptr = self.write_string(query).await?;
-            let signed_len = text_search
-                .call_async(&mut self.store, (query_ptr, page))
-                .await?;
+            let signed_len = self.call(text_search, (query_ptr, page)).await?;
             Ok(signed_len)
         } else {
-            Err(error::Error::NotSupported(error::AffectedFunction::Search))
+            Err(error::Error::NotSupported(
+                error::AffectedFunction::TextSearchUrl,
+            ))
         }
     }
 
     pub async fn text_search_url(&mut self, query: &str, page: i32) -> error::Result<String> {
+        self.prepare().await?;
         let signed_len = self.call_text_search_url(query, page).await?;
         self.parse_string_result::<QuelleError>(signed_len).await
     }
@@ -351,12 +741,12 @@ where
     async fn call_text_search(&mut self, query: &str, page: i32) -> error::Result<i32> {
         if let Some(text_search) = self.functions.text_search.clone() {
             let query_ptr = self.write_string(query).await?;
-            let signed_len = text_search
-                .call_async(&mut self.store, (query_ptr, page))
-                .await?;
+            let signed_len = self.call(text_search, (query_ptr, page)).await?;
             Ok(signed_len)
         } else {
-            Err(error::Error::NotSupported(error::AffectedFunction::Search))
+            Err(error::Error::NotSupported(
+                error::AffectedFunction::TextSearch,
+            ))
         }
     }
 
@@ -365,6 +755,7 @@ where
         query: &str,
         page: i32,
     ) -> crate::error::Result<Vec<BasicNovel>> {
+        self.prepare().await?;
         let signed_len = self.call_text_search(query, page).await?;
         self.parse_result::<Vec<BasicNovel>, QuelleError>(signed_len)
             .await
@@ -375,14 +766,10 @@ where
         query: &str,
         page: i32,
     ) -> error::Result<MemLoc> {
+        self.prepare().await?;
         let len = self.call_text_search(query, page).await?;
-        let offset = self
-            .functions
-            .last_result
-            .call_async(&mut self.store, ())
-            .await?;
-        let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-        Ok(MemLoc { offset, ptr, len })
+        let offset = self.call(self.functions.last_result.clone(), ()).await?;
+        self.memloc(offset, len)
     }
 
     // --------------------------------------------------------------------------------
@@ -390,31 +777,35 @@ where
     // --------------------------------------------------------------------------------
 
     pub fn filter_search_supported(&self) -> bool {
-        self.functions.filter_options.is_some()
+        self.capabilities.supports(Trait::FilterSearch)
     }
 
     pub async fn filter_options(&mut self) -> error::Result<FieldMap> {
+        self.prepare().await?;
         let Some(filter_options) = self.functions.filter_options.clone() else {
-            return Err(error::Error::NotSupported(error::AffectedFunction::Search));
+            return Err(error::Error::NotSupported(
+                error::AffectedFunction::FilterOptions,
+            ));
         };
 
-        let offset = filter_options.call_async(&mut self.store, ()).await?;
+        let offset = self.call(filter_options, ()).await?;
         let len = self.stack_pop().await?;
-        let bytes = self.read_bytes_with_len(offset, len as usize);
+        let bytes = self.read_bytes_with_len(offset, len as usize)?;
         let options = serde_json::from_slice(bytes).map_err(|_| Error::DeserializeError);
         self.dealloc_memory(offset, len).await?;
         options
     }
 
     pub async fn filter_search_url(&mut self, params: &str, page: i32) -> error::Result<String> {
+        self.prepare().await?;
         let Some(filter_search_url) = self.functions.filter_search_url.clone() else {
-            return Err(error::Error::NotSupported(error::AffectedFunction::Search));
+            return Err(error::Error::NotSupported(
+                error::AffectedFunction::FilterSearchUrl,
+            ));
         };
 
         let params_ptr = self.write_string(params).await?;
-        let len = filter_search_url
-            .call_async(&mut self.store, (params_ptr, page))
-            .await?;
+        let len = self.call(filter_search_url, (params_ptr, page)).await?;
 
         self.parse_string_result::<QuelleError>(len).await
     }
@@ -424,34 +815,168 @@ where
         params: &str,
         page: i32,
     ) -> error::Result<Vec<BasicNovel>> {
+        self.prepare().await?;
         let Some(filter_search) = self.functions.filter_search.clone() else {
-            return Err(error::Error::NotSupported(error::AffectedFunction::Search));
+            return Err(error::Error::NotSupported(
+                error::AffectedFunction::FilterSearch,
+            ));
         };
 
         let params_ptr = self.write_string(params).await?;
-        let len = filter_search
-            .call_async(&mut self.store, (params_ptr, page))
-            .await?;
+        let len = self.call(filter_search, (params_ptr, page)).await?;
 
         self.parse_result::<Vec<BasicNovel>, QuelleError>(len).await
     }
 
+    // --------------------------------------------------------------------------------
+    // Images
+    // --------------------------------------------------------------------------------
+
+    pub fn image_request_supported(&self) -> bool {
+        self.capabilities.supports(Trait::Image)
+    }
+
+    /// The request the extension fetches the image at the url with
+    pub async fn image_request(&mut self, url: &str) -> error::Result<Request> {
+        self.prepare().await?;
+        let Some(image_request) = self.functions.image_request.clone() else {
+            return Err(error::Error::NotSupported(
+                error::AffectedFunction::ImageRequest,
+            ));
+        };
+
+        let iptr = self.write_string(url).await?;
+        let signed_len = self.call(image_request, iptr).await?;
+        self.parse_result::<Request, QuelleError>(signed_len).await
+    }
+
+    /// Fetch the image at the url, such as the cover of a novel
+    ///
+    /// The request comes from [Runtime::image_request], falling back to a plain
+    /// `GET` for extensions without one, and goes through the same cookies, rate
+    /// limiter and retries as the requests of the extension. Images may come from
+    /// any public host rather than only the hosts of the extension.
+    pub async fn fetch_image(&mut self, url: &str) -> error::Result<Response> {
+        let request = match self.image_request(url).await {
+            Err(Error::NotSupported(_)) => Request::get(url.to_string()),
+            result => result?,
+        };
+        module::http::send_image(self.store().data(), request).await
+    }
+
     // --------------------------------------------------------------------------------
     // Helpers
     // --------------------------------------------------------------------------------
 
+    /// Recover from a previous trap and refill the limits before a new call into the extension
+    async fn prepare(&mut self) -> error::Result<()> {
+        if self.poisoned {
+            self.recover().await?;
+        }
+        let limits = self.limits.clone();
+        reset_limits(self.store_mut(), &limits)
+    }
+
+    /// Replace the poisoned instance with a new one and run the last setup again
+    ///
+    /// The instance gets a new store holding the state of the old one, so the
+    /// memory of the trapped instance is freed rather than kept in the store.
+    async fn recover(&mut self) -> error::Result<()> {
+        log::warn!("extension trapped, creating a new instance");
+        let state = self
+            .store
+            .take()
+            .expect("the store is only taken while recovering")
+            .into_data();
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limiter);
+        reset_limits(&mut store, &self.limits)?;
+
+        let (instance, memory, functions) =
+            match instantiate(&self.linker, &self.module, &mut store).await {
+                Ok(instance) => instance,
+                Err(error) => {
+                    // Keep the store, so the next call can try to recover again
+                    self.store = Some(store);
+                    return Err(error);
+                }
+            };
+        self.store = Some(store);
+        self.instance = instance;
+        self.memory = memory;
+        self.functions = functions;
+        self.poisoned = false;
+
+        if let Some(config) = self.config.clone() {
+            self.call_setup(&config).await?;
+        }
+
+        self.apply_level_filter().await
+    }
+
+    fn store(&self) -> &Store<State<D>> {
+        self.store
+            .as_ref()
+            .expect("the store is only taken while recovering")
+    }
+
+    fn store_mut(&mut self) -> &mut Store<State<D>> {
+        self.store
+            .as_mut()
+            .expect("the store is only taken while recovering")
+    }
+
+    /// Call into the extension, marking the instance as poisoned if it traps
+    ///
+    /// The epoch deadline only interrupts guest code, so the whole call is also
+    /// bounded by the deadline to stop one waiting on slow host calls. Exceeding
+    /// a limit leaves the instance halfway through the call, so it is poisoned as
+    /// well. Other errors returned by host functions leave the instance as it is.
+    async fn call<P, R>(&mut self, func: TypedFunc<P, R>, params: P) -> error::Result<R>
+    where
+        P: WasmParams,
+        R: WasmResults,
+    {
+        let deadline = self.limits.deadline;
+        let call = func.call_async(self.store_mut(), params);
+        let result = match deadline {
+            Some(deadline) => match tokio::time::timeout(deadline, call).await {
+                Ok(result) => result,
+                Err(_) => {
+                    self.poisoned = true;
+                    return Err(Error::LimitExceeded(Limit::Deadline));
+                }
+            },
+            None => call.await,
+        };
+
+        result.map_err(|error| {
+            let trapped = error.downcast_ref::<Trap>().is_some();
+            let error = Error::from(error);
+            if trapped || matches!(error, Error::LimitExceeded(_)) {
+                self.poisoned = true;
+            }
+            error
+        })
+    }
+
     async fn read_bytes(&mut self, offset: i32) -> crate::error::Result<&[u8]> {
         let len = self.stack_pop().await? as usize;
-        let bytes = self.read_bytes_with_len(offset, len);
-        Ok(bytes)
+        self.read_bytes_with_len(offset, len)
     }
 
-    fn read_bytes_with_len(&self, offset: i32, len: usize) -> &[u8] {
-        unsafe {
-            let ptr = self.memory.data_ptr(&self.store).offset(offset as isize);
-            let bytes = slice::from_raw_parts(ptr, len);
-            bytes
-        }
+    fn read_bytes_with_len(&self, offset: i32, len: usize) -> error::Result<&[u8]> {
+        module::utils::checked_slice(self.memory.data(self.store()), offset, len)
+    }
+
+    /// Locate `len` bytes at `offset` after checking they are within the memory
+    fn memloc(&self, offset: i32, len: i32) -> error::Result<MemLoc> {
+        let bytes = self.read_bytes_with_len(offset, len.unsigned_abs() as usize)?;
+        Ok(MemLoc {
+            offset,
+            ptr: bytes.as_ptr() as *mut u8,
+            len,
+        })
     }
 
     async fn parse_result<T, E>(&mut self, signed_len: i32) -> crate::error::Result<T>
@@ -527,7 +1052,7 @@ where
         f: impl Fn(&[u8]) -> crate::error::Result<T>,
     ) -> crate::error::Result<T> {
         let offset = self.last_result().await?;
-        let bytes = self.read_bytes_with_len(offset, len);
+        let bytes = self.read_bytes_with_len(offset, len)?;
 
         let out = f(bytes);
 
@@ -537,63 +1062,36 @@ where
         out
     }
 
-    async fn write_serialize<T>(&mut self, value: &T) -> crate::error::Result<i32>
-    where
-        T: Serialize,
-    {
-        let string = serde_json::to_string(value).map_err(|_| Error::SerializeError)?;
-        return self.write_string(&string).await;
-    }
-
     async fn write_string(&mut self, value: &str) -> crate::error::Result<i32> {
         let ptr = self.alloc_memory(value.len() as i32).await?;
         self.stack_push(value.len() as i32).await?;
 
-        self.memory
-            .write(&mut self.store, ptr as usize, value.as_bytes())
+        let memory = self.memory;
+        memory
+            .write(self.store_mut(), ptr as usize, value.as_bytes())
             .map_err(|_| Error::MemoryAccessError)?;
 
         Ok(ptr)
     }
 
     async fn alloc_memory(&mut self, len: i32) -> crate::error::Result<i32> {
-        self.functions
-            .alloc
-            .call_async(&mut self.store, len)
-            .await
-            .map_err(|e| e.into())
+        self.call(self.functions.alloc.clone(), len).await
     }
 
     pub async fn dealloc_memory(&mut self, ptr: i32, len: i32) -> crate::error::Result<()> {
-        self.functions
-            .dealloc
-            .call_async(&mut self.store, (ptr, len))
-            .await
-            .map_err(|e| e.into())
+        self.call(self.functions.dealloc.clone(), (ptr, len)).await
     }
 
     async fn stack_push(&mut self, size: i32) -> crate::error::Result<()> {
-        self.functions
-            .stack_push
-            .call_async(&mut self.store, size)
-            .await
-            .map_err(|e| e.into())
+        self.call(self.functions.stack_push.clone(), size).await
     }
 
     async fn stack_pop(&mut self) -> crate::error::Result<i32> {
-        self.functions
-            .stack_pop
-            .call_async(&mut self.store, ())
-            .await
-            .map_err(|e| e.into())
+        self.call(self.functions.stack_pop.clone(), ()).await
     }
 
     async fn last_result(&mut self) -> error::Result<i32> {
-        self.functions
-            .last_result
-            .call_async(&mut self.store, ())
-            .await
-            .map_err(|e| e.into())
+        self.call(self.functions.last_result.clone(), ()).await
     }
 }
 
@@ -603,3 +1101,287 @@ pub struct MemLoc {
     pub ptr: *mut u8,
     pub len: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    async fn runtime() -> Runtime<DefaultImpl> {
+        let data = DefaultImpl::default();
+
+        Runtime::builder()
+            .http_client(data.client.clone())
+            .build(&fixture("malformed.wat"), data)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_reject_out_of_bounds_results() {
+        let mut runtime = runtime().await;
+
+        let meta = runtime.meta().await;
+        assert!(matches!(meta, Err(Error::MemoryAccessError)));
+
+        let novel = runtime.fetch_novel("https://example.com").await;
+        assert!(matches!(novel, Err(Error::MemoryAccessError)));
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_utf8_results() {
+        let mut runtime = runtime().await;
+
+        let url = runtime.text_search_url("query", 1).await;
+        assert!(matches!(url, Err(Error::Utf8Error(_))));
+    }
+
+    #[tokio::test]
+    async fn should_survive_malformed_host_calls() {
+        let mut runtime = runtime().await;
+
+        let content = runtime.fetch_chapter_content("https://example.com").await;
+        assert!(matches!(content, Err(Error::FailedResultAttempt)));
+
+        let popular = runtime.popular(1).await;
+        assert!(matches!(popular, Err(Error::MemoryAccessError)));
+        assert!(!runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_recover_after_trap() {
+        let mut runtime = Runtime::builder()
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+
+        runtime.setup(&ExtensionConfig::default()).await.unwrap();
+
+        assert!(runtime.fetch_novel("https://example.com").await.is_err());
+        assert!(runtime.is_poisoned());
+
+        let url = runtime.text_search_url("query", 1).await.unwrap();
+        assert_eq!(url, "");
+        assert!(!runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_recover_repeatedly_within_limits() {
+        let mut runtime = Runtime::builder()
+            .max_memory(1 << 16)
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+
+        runtime.setup(&ExtensionConfig::default()).await.unwrap();
+        for _ in 0..100 {
+            assert!(runtime.fetch_novel("https://example.com").await.is_err());
+            assert!(runtime.is_poisoned());
+        }
+
+        let url = runtime.text_search_url("query", 1).await.unwrap();
+        assert_eq!(url, "");
+        assert!(!runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_capture_output_and_change_level() {
+        let buffer = output::OutputBuffer::default();
+        let mut runtime = Runtime::builder()
+            .output(buffer.output())
+            .build(&fixture("output.wat"), ())
+            .await
+            .unwrap();
+
+        runtime.setup(&ExtensionConfig::default()).await.unwrap();
+        runtime.set_level_filter(LevelFilter::Debug).await.unwrap();
+        runtime.text_search_url("query", 1).await.unwrap();
+
+        assert_eq!(buffer.take(output::Stream::Stdout), "hello");
+        assert_eq!(buffer.take(output::Stream::Stderr), "world\n4");
+    }
+
+    #[tokio::test]
+    async fn should_name_missing_function() {
+        let mut runtime = Runtime::builder()
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+
+        let capabilities = runtime.capabilities();
+        assert_eq!(
+            capabilities.exports,
+            vec!["abi_version", "setup", "text_search_url"]
+        );
+        assert_eq!(capabilities.traits, vec![Trait::Basic]);
+        assert!(!runtime.text_search_supported());
+
+        let result = runtime.text_search("query", 1).await;
+        assert!(matches!(
+            result,
+            Err(Error::NotSupported(error::AffectedFunction::TextSearch))
+        ));
+
+        let result = runtime.filter_options().await;
+        assert!(matches!(
+            result,
+            Err(Error::NotSupported(error::AffectedFunction::FilterOptions))
+        ));
+    }
+
+    struct Images;
+
+    #[async_trait::async_trait]
+    impl HttpClient for Images {
+        async fn send(&self, request: Request) -> Result<Response, RequestError> {
+            let status = if request.url.ends_with("/cover.png") {
+                200
+            } else {
+                404
+            };
+            Ok(Response {
+                status,
+                url: request.url,
+                body: Some(b"\x89PNG".to_vec()),
+                headers: Default::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fetch_images_from_other_hosts() {
+        let network = NetworkPolicy::default();
+        network.hint(&Meta {
+            base_urls: vec![String::from("https://example.com")],
+            ..Default::default()
+        });
+        let mut runtime = Runtime::builder()
+            .http_client(Images)
+            .network(network.clone())
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+
+        assert!(network.check("https://cdn.example.net/cover.png").is_err());
+        let cover = runtime
+            .fetch_image("https://cdn.example.net/cover.png")
+            .await
+            .unwrap();
+        assert_eq!(cover.body.as_deref(), Some(&b"\x89PNG"[..]));
+
+        let private = runtime.fetch_image("http://127.0.0.1/cover.png").await;
+        assert!(matches!(
+            private,
+            Err(Error::ReturnedError(QuelleError::RequestFailed(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_fetch_images_without_image_request() {
+        let mut runtime = Runtime::builder()
+            .http_client(Images)
+            .network(NetworkPolicy::default().allow_any_host().allow_private())
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+        assert!(!runtime.image_request_supported());
+
+        let image = runtime
+            .fetch_image("http://127.0.0.1/cover.png")
+            .await
+            .unwrap();
+        assert_eq!(image.body.as_deref(), Some(&b"\x89PNG"[..]));
+
+        let missing = runtime.fetch_image("http://127.0.0.1/missing.png").await;
+        assert!(matches!(
+            missing,
+            Err(Error::ReturnedError(QuelleError::RequestFailed(_)))
+        ));
+    }
+
+    /// Only answers requests to the second mirror
+    struct SecondMirror;
+
+    #[async_trait::async_trait]
+    impl HttpClient for SecondMirror {
+        async fn send(&self, request: Request) -> Result<Response, RequestError> {
+            if !request.url.starts_with("http://127.0.0.2") {
+                return Err(RequestError {
+                    kind: RequestErrorKind::Connect,
+                    url: Some(request.url),
+                    message: String::from("connection refused"),
+                });
+            }
+            Ok(Response {
+                status: 200,
+                url: request.url,
+                body: None,
+                headers: Default::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_mirror() {
+        let mirrors = Mirrors::default();
+        let mut runtime = Runtime::builder()
+            .http_client(SecondMirror)
+            .network(NetworkPolicy::default().allow_any_host().allow_private())
+            .retry_policy(RetryPolicy::none())
+            .mirrors(mirrors.clone())
+            .build(&fixture("trap.wat"), ())
+            .await
+            .unwrap();
+        mirrors.hint(&Meta {
+            base_urls: vec![
+                String::from("http://127.0.0.1"),
+                String::from("http://127.0.0.2"),
+            ],
+            ..Default::default()
+        });
+
+        let image = runtime.fetch_image("http://127.0.0.1/cover.png").await;
+        assert_eq!(image.unwrap().url, "http://127.0.0.2/cover.png");
+        assert_eq!(mirrors.current().as_deref(), Some("http://127.0.0.2"));
+
+        let missing = runtime.fetch_image("http://127.0.0.3/cover.png").await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_check_abi_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let wat = std::fs::read_to_string(fixture("trap.wat")).unwrap();
+        let export = |version: u32| {
+            format!("(func (export \"abi_version\") (result i32) (i32.const {version}))")
+        };
+        let with_version = |version: Option<u32>| {
+            let path = dir.path().join(format!("abi-{version:?}.wat"));
+            let replacement = version.map(export).unwrap_or_default();
+            std::fs::write(&path, wat.replacen(&export(2), &replacement, 1)).unwrap();
+            path
+        };
+
+        for version in [1, ABI_VERSION] {
+            let runtime = Runtime::builder()
+                .build(&with_version(Some(version)), ())
+                .await
+                .unwrap();
+            assert_eq!(runtime.capabilities().abi_version, version);
+        }
+
+        let runtime = Runtime::builder().build(&with_version(Some(99)), ()).await;
+        assert!(matches!(runtime, Err(Error::UnsupportedAbi(99))));
+
+        // Extensions built before the version was exported send json requests
+        let runtime = Runtime::builder().build(&with_version(None), ()).await;
+        assert!(matches!(runtime, Err(Error::UnsupportedAbi(0))));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{fmt::Display, thread, time::Duration};
+
+use wasmtime::{Engine, ResourceLimiter};
+
+use crate::error::Error;
+
+/// The interval at which the engine epoch is incremented
+pub(crate) const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Limits applied to every call into the extension
+///
+/// A call that goes over any of the limits fails with [Error::LimitExceeded].
+#[derive(Debug, Default, Clone)]
+pub struct Limits {
+    /// The maximum wall-clock time a single call may take, including host calls
+    pub deadline: Option<Duration>,
+
+    /// The amount of fuel given to a single call
+    pub fuel: Option<u64>,
+
+    /// The maximum size of the extension's linear memory in bytes
+    pub max_memory: Option<usize>,
+}
+
+impl Limits {
+    /// The deadline rounded up to whole epoch ticks
+    pub(crate) fn deadline_ticks(&self) -> Option<u64> {
+        self.deadline.map(|deadline| {
+            let ticks = deadline.as_nanos().div_ceil(EPOCH_TICK.as_nanos());
+            ticks.max(1) as u64
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Deadline,
+    Fuel,
+    Memory,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Limit::Deadline => "deadline",
+            Limit::Fuel => "fuel",
+            Limit::Memory => "memory",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+/// Rejects linear memory growth past [Limits::max_memory]
+pub(crate) struct MemoryLimiter {
+    pub max_memory: Option<usize>,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        match self.max_memory {
+            Some(max_memory) if desired > max_memory => {
+                Err(Error::LimitExceeded(Limit::Memory).into())
+            }
+            _ => Ok(true),
+        }
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: u32,
+        _desired: u32,
+        _maximum: Option<u32>,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Increment the epoch of the engine every [EPOCH_TICK] until it is dropped
+pub(crate) fn spawn_epoch_ticker(engine: &Engine) {
+    let engine = engine.weak();
+    thread::spawn(move || loop {
+        thread::sleep(EPOCH_TICK);
+        match engine.upgrade() {
+            Some(engine) => engine.increment_epoch(),
+            None => break,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use quelle_core::prelude::{Request, RequestError, RequestErrorKind, Response};
+
+    use super::*;
+    use crate::{client::HttpClient, network::NetworkPolicy, retry::RetryPolicy, Runtime};
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/limits.wat")
+    }
+
+    #[tokio::test]
+    async fn should_stop_call_after_deadline() {
+        let mut runtime = Runtime::builder()
+            .deadline(Duration::from_millis(50))
+            .build(&fixture(), ())
+            .await
+            .unwrap();
+
+        let result = runtime.fetch_novel("https://example.com").await;
+        assert!(matches!(result, Err(Error::LimitExceeded(Limit::Deadline))));
+    }
+
+    struct Slow;
+
+    #[async_trait::async_trait]
+    impl HttpClient for Slow {
+        async fn send(&self, _request: Request) -> Result<Response, RequestError> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Err(RequestError {
+                kind: RequestErrorKind::Timeout,
+                url: None,
+                message: String::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_stop_call_waiting_on_host() {
+        let mut runtime = Runtime::builder()
+            .deadline(Duration::from_millis(50))
+            .http_client(Slow)
+            .network(NetworkPolicy::default().allow_any_host().allow_private())
+            .retry_policy(RetryPolicy::none())
+            .build(&fixture(), ())
+            .await
+            .unwrap();
+
+        let result = runtime.popular(1).await;
+        assert!(matches!(result, Err(Error::LimitExceeded(Limit::Deadline))));
+        assert!(runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_stop_call_out_of_fuel() {
+        let mut runtime = Runtime::builder()
+            .fuel(100_000)
+            .build(&fixture(), ())
+            .await
+            .unwrap();
+
+        let result = runtime.fetch_novel("https://example.com").await;
+        assert!(matches!(result, Err(Error::LimitExceeded(Limit::Fuel))));
+        assert!(runtime.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn should_refill_fuel_for_each_call() {
+        let mut runtime = Runtime::builder()
+            .fuel(100_000)
+            .build(&fixture(), ())
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            let result = runtime.fetch_chapter_content("https://example.com").await;
+            assert!(matches!(result, Err(Error::FailedResultAttempt)));
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_memory_growth_past_limit() {
+        let mut runtime = Runtime::builder()
+            .max_memory(16 * 1024 * 1024)
+            .build(&fixture(), ())
+            .await
+            .unwrap();
+
+        let result = runtime.fetch_chapter_content("https://example.com").await;
+        assert!(matches!(result, Err(Error::LimitExceeded(Limit::Memory))));
+        assert!(runtime.is_poisoned());
+    }
+
+    #[test]
+    fn should_round_deadline_up_to_ticks() {
+        let limits = Limits {
+            deadline: Some(Duration::from_millis(15)),
+            ..Default::default()
+        };
+        assert_eq!(limits.deadline_ticks(), Some(2));
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::{Arc, Mutex};
+
+use log::info;
+use quelle_core::prelude::{
+    is_downgrade, strip_base_url, Meta, RequestError, RequestErrorKind, Response,
+};
+
+/// The mirrors of an extension, moving its requests to another one when the current is down
+///
+/// The [Meta::base_urls] of an extension are equivalent mirrors serving the same
+/// paths. Requests to any of them are sent to the current mirror, which starts as
+/// the first base url. When the current mirror can not be reached, the request is
+/// sent to the other base urls in order and the first one answering becomes the
+/// current mirror. Clones share the mirrors, so every runtime of a
+/// [RuntimePool](crate::pool::RuntimePool) moves at once.
+#[derive(Clone, Default)]
+pub struct Mirrors {
+    inner: Arc<Mutex<Option<MirrorsInner>>>,
+}
+
+struct MirrorsInner {
+    base_urls: Vec<String>,
+    current: usize,
+}
+
+impl Mirrors {
+    /// Use the base urls of the extension as its mirrors
+    ///
+    /// The current mirror is kept when the base urls did not change.
+    pub fn hint(&self, meta: &Meta) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner
+            .as_ref()
+            .is_some_and(|inner| inner.base_urls == meta.base_urls)
+        {
+            return;
+        }
+
+        *inner = Some(MirrorsInner {
+            base_urls: meta.base_urls.clone(),
+            current: 0,
+        });
+    }
+
+    /// The base url requests are currently sent to, known once the meta was read
+    pub fn current(&self) -> Option<String> {
+        let inner = self.inner.lock().unwrap();
+        let inner = inner.as_ref()?;
+        inner.base_urls.get(inner.current).cloned()
+    }
+
+    /// Make the base url the current mirror, such as one that worked in an earlier run
+    ///
+    /// Returns whether the base url is one of the mirrors.
+    pub fn prefer(&self, base_url: &str) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(inner) = inner.as_mut() else {
+            return false;
+        };
+
+        match inner.position(base_url) {
+            Some(index) => {
+                inner.current = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The url on each mirror to try in order, starting with the current one
+    ///
+    /// Empty when the url does not belong to any of the mirrors. An `https` url
+    /// never falls back to an `http` mirror, which would send its cookies in the clear.
+    pub(crate) fn candidates(&self, url: &str) -> Vec<(String, String)> {
+        let inner = self.inner.lock().unwrap();
+        let Some(inner) = inner.as_ref() else {
+            return vec![];
+        };
+
+        let Some(rest) = inner
+            .base_urls
+            .iter()
+            .find_map(|base_url| strip_base_url(url, base_url))
+        else {
+            return vec![];
+        };
+
+        let current = inner.base_urls.get(inner.current).into_iter();
+        let others = inner
+            .base_urls
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != inner.current)
+            .map(|(_, base_url)| base_url);
+
+        current
+            .chain(others)
+            .filter(|base_url| !is_downgrade(url, base_url))
+            .map(|base_url| {
+                let url = format!("{}{rest}", base_url.trim_end_matches('/'));
+                (base_url.clone(), url)
+            })
+            .collect()
+    }
+
+    /// Keep sending the requests to the base url after it answered
+    pub(crate) fn answered(&self, base_url: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(inner) = inner.as_mut() else {
+            return;
+        };
+
+        if let Some(index) = inner.position(base_url) {
+            if index != inner.current {
+                info!("switched to the mirror '{base_url}'");
+                inner.current = index;
+            }
+        }
+    }
+}
+
+impl MirrorsInner {
+    fn position(&self, base_url: &str) -> Option<usize> {
+        let base_url = base_url.trim_end_matches('/');
+        self.base_urls
+            .iter()
+            .position(|url| url.trim_end_matches('/') == base_url)
+    }
+}
+
+/// Whether the result tells the mirror is unreachable rather than the page missing
+pub(crate) fn is_down(result: &Result<Response, RequestError>) -> bool {
+    match result {
+        Ok(response) => matches!(response.status, 502..=504 | 520..=523),
+        Err(RequestError { kind, .. }) => {
+            matches!(kind, RequestErrorKind::Connect | RequestErrorKind::Timeout)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirrors() -> Mirrors {
+        let mirrors = Mirrors::default();
+        mirrors.hint(&Meta {
+            base_urls: vec![
+                String::from("https://a.com"),
+                String::from("https://b.com/"),
+                String::from("https://c.com"),
+            ],
+            ..Default::default()
+        });
+        mirrors
+    }
+
+    #[test]
+    fn should_start_with_current_mirror() {
+        let mirrors = mirrors();
+        assert_eq!(mirrors.current().as_deref(), Some("https://a.com"));
+
+        let urls = mirrors
+            .candidates("https://b.com/novel/1")
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.com/novel/1",
+                "https://b.com/novel/1",
+                "https://c.com/novel/1"
+            ]
+        );
+        assert!(mirrors.candidates("https://other.com/novel/1").is_empty());
+
+        mirrors.answered("https://c.com");
+        let (base_url, url) = mirrors.candidates("https://a.com/novel/1").remove(0);
+        assert_eq!(base_url, "https://c.com");
+        assert_eq!(url, "https://c.com/novel/1");
+    }
+
+    #[test]
+    fn should_not_fall_back_to_plain_http() {
+        let mirrors = Mirrors::default();
+        mirrors.hint(&Meta {
+            base_urls: vec![String::from("https://a.com"), String::from("http://a.com")],
+            ..Default::default()
+        });
+
+        let urls = |url| {
+            mirrors
+                .candidates(url)
+                .into_iter()
+                .map(|(_, url)| url)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(urls("https://a.com/novel/1"), vec!["https://a.com/novel/1"]);
+
+        mirrors.prefer("http://a.com");
+        assert_eq!(urls("https://a.com/novel/1"), vec!["https://a.com/novel/1"]);
+        assert_eq!(
+            urls("http://a.com/novel/1"),
+            vec!["http://a.com/novel/1", "https://a.com/novel/1"]
+        );
+    }
+
+    #[test]
+    fn should_prefer_known_mirrors() {
+        let mirrors = mirrors();
+        assert!(mirrors.prefer("https://b.com"));
+        assert_eq!(mirrors.current().as_deref(), Some("https://b.com/"));
+        assert!(!mirrors.prefer("https://other.com"));
+
+        // Reading the same meta again keeps the mirror
+        mirrors.hint(&Meta {
+            base_urls: vec![
+                String::from("https://a.com"),
+                String::from("https://b.com/"),
+                String::from("https://c.com"),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(mirrors.current().as_deref(), Some("https://b.com/"));
+        assert!(!Mirrors::default().prefer("https://a.com"));
+    }
+}
@@ -1,52 +1,411 @@
-use std::future::Future;
+use std::{future::Future, time::Duration};
 
-use log::{debug, trace};
-use quelle_core::prelude::{Body, Request, RequestError, RequestErrorKind, Response};
+use futures_util::{stream, StreamExt, TryStreamExt};
+
+use log::{debug, info, warn};
+use quelle_core::{
+    prelude::{
+        Body, HeaderMap, Method, QuelleError, Request, RequestError, RequestErrorKind, Response,
+    },
+    transport,
+};
+use reqwest::{header::CONTENT_TYPE, Url};
+use serde::Deserialize;
 use wasmtime::{Caller, Memory};
 
 use crate::{
-    data::DefaultImpl,
-    module::utils::{read_str_with_len, write_str},
+    data::State,
+    error::{self, Error},
+    mirror,
+    module::utils::{get_memory, read_bytes_with_len, write_bytes},
+    network::{self, NetworkPolicy},
 };
 
-pub fn send_request_noop<'a, D>(
-    _caller: Caller<'a, D>,
-    _ptr: i32,
-    _len: i32,
-) -> Box<dyn Future<Output = i32> + Send> {
-    Box::new(async move { 0 })
+/// Answer the request of the extension with [respond]
+pub fn send_request<'a, D: Send + Sync>(
+    mut caller: Caller<'a, State<D>>,
+    ptr: i32,
+    len: i32,
+) -> Box<dyn Future<Output = anyhow::Result<i32>> + Send + 'a> {
+    Box::new(async move {
+        let memory = get_memory(&mut caller)?;
+        let response = match read_request(&mut caller, ptr, len, &memory)? {
+            Ok(request) => respond(caller.data(), request).await?,
+            Err(e) => Err(e),
+        };
+        Ok(write_response(&mut caller, &memory, &response).await?)
+    })
 }
 
-pub fn send_request<'a>(
-    mut caller: Caller<'a, DefaultImpl>,
+/// Answer the batch of requests of the extension concurrently with [send_all]
+pub fn send_requests<'a, D: Send + Sync>(
+    mut caller: Caller<'a, State<D>>,
     ptr: i32,
     len: i32,
-) -> Box<dyn Future<Output = i32> + Send + 'a> {
+) -> Box<dyn Future<Output = anyhow::Result<i32>> + Send + 'a> {
     Box::new(async move {
-        let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-        let request = read_request(&mut caller, ptr, len, &memory);
-        let client = &caller.data().client;
-        let response = send_request_reqwest::<DefaultImpl>(client, request).await;
-        let response = parse_response(response).await;
-        let json = serde_json::to_string(&response).unwrap();
-        write_str(&mut caller, &memory, json.as_str()).await
+        let memory = get_memory(&mut caller)?;
+        let responses = match read_requests(&mut caller, ptr, len, &memory)? {
+            Ok(requests) => send_all(caller.data(), requests).await?,
+            Err(e) => vec![Err(e)],
+        };
+        Ok(write_responses(&mut caller, &memory, &responses).await?)
     })
 }
 
-pub fn read_request<D>(caller: &mut Caller<'_, D>, ptr: i32, len: i32, memory: &Memory) -> Request {
-    let request_data = read_str_with_len(caller, &memory, ptr, len as usize);
-    let request_data = serde_json::from_str::<Request>(request_data).unwrap();
+/// Answer the requests concurrently with [respond], keeping the order of the responses
+///
+/// At most [State::max_concurrent_requests] of the requests are in flight at once,
+/// on top of the limits of the [RateLimiter](crate::rate_limit::RateLimiter).
+pub async fn send_all<D>(
+    state: &State<D>,
+    requests: Vec<Request>,
+) -> error::Result<Vec<Result<Response, RequestError>>> {
+    stream::iter(requests)
+        .map(|request| respond(state, request))
+        .buffered(state.max_concurrent_requests())
+        .try_collect()
+        .await
+}
+
+/// Answer the request of an image with [respond], failing unless the response is a success
+///
+/// Covers and inline images are often served by other sites than the extension,
+/// so images may come from any host. Loopback and private addresses stay blocked.
+pub async fn send_image<D>(state: &State<D>, request: Request) -> error::Result<Response> {
+    let network = state.network().clone().allow_any_host();
+    let response = respond_with(state, &network, request)
+        .await?
+        .and_then(|response| {
+            if response.is_success() {
+                return Ok(response);
+            }
+            Err(RequestError {
+                kind: RequestErrorKind::Status(response.status as u16),
+                message: format!("the image request failed with status {}", response.status),
+                url: Some(response.url),
+            })
+        });
+
+    response.map_err(|e| Error::ReturnedError(QuelleError::RequestFailed(e.into())))
+}
+
+/// Answer the request from the [Fixtures](crate::replay::Fixtures) of the runtime,
+/// or [send] it when there are none
+///
+/// Replayed requests skip the network entirely. A request without a recorded
+/// fixture fails the call with [Error::MissingFixture].
+pub async fn respond<D>(
+    state: &State<D>,
+    request: Request,
+) -> error::Result<Result<Response, RequestError>> {
+    respond_with(state, state.network(), request).await
+}
+
+/// [respond] checking the request against the network policy instead of the one of the extension
+async fn respond_with<D>(
+    state: &State<D>,
+    network: &NetworkPolicy,
+    request: Request,
+) -> error::Result<Result<Response, RequestError>> {
+    match state.fixtures() {
+        Some(fixtures) => fixtures.respond(state, network, request).await,
+        None => Ok(send_with(state, network, request).await),
+    }
+}
+
+/// Send the request with the cookies of the extension through the rate limiter,
+/// retrying transient failures
+///
+/// Every request and redirect is checked against the [NetworkPolicy] of the
+/// extension before it is handed to the [HttpClient](crate::client::HttpClient).
+/// Requests to the base urls of the extension go to its current mirror, falling
+/// back to the other [Mirrors](crate::mirror::Mirrors) when it is unreachable.
+pub async fn send<D>(state: &State<D>, request: Request) -> Result<Response, RequestError> {
+    send_with(state, state.network(), request).await
+}
+
+/// [send] checking the request against the network policy instead of the one of the extension
+pub(crate) async fn send_with<D>(
+    state: &State<D>,
+    network: &NetworkPolicy,
+    request: Request,
+) -> Result<Response, RequestError> {
+    let candidates = state.mirrors().candidates(&request.url);
+    let count = candidates.len();
+
+    for (attempt, (base_url, url)) in candidates.into_iter().enumerate() {
+        let mirrored = Request {
+            url,
+            ..request.clone()
+        };
+        let response = send_with_retries(state, network, mirrored).await;
+
+        if !mirror::is_down(&response) {
+            state.mirrors().answered(&base_url);
+            return response;
+        }
+        if attempt + 1 == count {
+            return response;
+        }
+        warn!("the mirror '{base_url}' is unreachable, trying the next one");
+    }
+
+    send_with_retries(state, network, request).await
+}
+
+async fn send_with_retries<D>(
+    state: &State<D>,
+    network: &NetworkPolicy,
+    request: Request,
+) -> Result<Response, RequestError> {
+    let policy = state.retry_policy();
+    let mut attempt = 1;
+
+    loop {
+        let response = fetch(state, network, request.clone()).await;
+
+        let Some(delay) = policy.delay(attempt, &response) else {
+            match &response {
+                _ if attempt == 1 => {}
+                Ok(response) if response.is_success() => {
+                    info!(
+                        "request to '{}' succeeded after {attempt} attempts",
+                        request.url
+                    )
+                }
+                _ => warn!(
+                    "request to '{}' gave up after {attempt} attempts",
+                    request.url
+                ),
+            }
+            return response;
+        };
+
+        let reason = match &response {
+            Ok(response) => format!("status {}", response.status),
+            Err(e) => format!("{:?} error", e.kind),
+        };
+        warn!(
+            "request to '{}' failed with {reason}, retrying in {delay:?} (attempt {attempt} of {})",
+            request.url, policy.max_attempts
+        );
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Send a single attempt of the request, following its redirects
+async fn fetch<D>(
+    state: &State<D>,
+    network: &NetworkPolicy,
+    mut request: Request,
+) -> Result<Response, RequestError> {
+    let mut redirects = 0;
+
+    loop {
+        network.check(&request.url)?;
+
+        let mut hop = request.clone();
+        if let Some(cookie) = state.cookie_jar().header(&request.url) {
+            let headers = hop.headers.get_or_insert_with(Vec::new);
+            if !headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+            {
+                headers.push((String::from("Cookie"), cookie));
+            }
+        }
+
+        let permit = state.rate_limiter().acquire(&request.url).await;
+        let response = state
+            .http_client()
+            .send_limited(hop, network.response_size_limit())
+            .await;
+        drop(permit);
+
+        let response = response?;
+        state.cookie_jar().store(&response.url, response.cookies());
+
+        let Some(next) = redirect(&request, &response) else {
+            return Ok(response);
+        };
+
+        redirects += 1;
+        if redirects > network.redirect_limit() {
+            return Err(RequestError {
+                kind: RequestErrorKind::Redirect,
+                url: Some(response.url),
+                message: format!("exceeded {} redirects", network.redirect_limit()),
+            });
+        }
+
+        debug!(
+            "following redirect from '{}' to '{}'",
+            response.url, next.url
+        );
+        request = next;
+    }
+}
+
+/// The request to send next if the response redirects
+fn redirect(request: &Request, response: &Response) -> Option<Request> {
+    if !matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+
+    let location = response.headers.get("location")?;
+    let current = Url::parse(&response.url).ok()?;
+    let url = current.join(location).ok()?;
+
+    let mut next = request.clone();
+    next.params = None;
+
+    // Browsers turn every redirected POST into a GET except for 307 and 308
+    let keep_method = matches!(response.status, 307 | 308)
+        || matches!(request.method, Method::Get)
+        || (response.status != 303 && !matches!(request.method, Method::Post));
+    if !keep_method {
+        next.method = Method::Get;
+        next.data = None;
+    }
+
+    if url.host_str() != current.host_str() {
+        if let Some(headers) = &mut next.headers {
+            headers.retain(|(name, _)| !name.eq_ignore_ascii_case("authorization"));
+        }
+    }
+
+    next.url = url.to_string();
+    Some(next)
+}
+
+/// The layout of [Request] before ABI version 2 added its timeout
+#[derive(Deserialize)]
+struct RequestV1 {
+    method: Method,
+    url: String,
+    params: Option<Vec<(String, String)>>,
+    data: Option<Body>,
+    headers: Option<Vec<(String, String)>>,
+}
+
+impl From<RequestV1> for Request {
+    fn from(value: RequestV1) -> Self {
+        Request {
+            method: value.method,
+            url: value.url,
+            params: value.params,
+            data: value.data,
+            headers: value.headers,
+            timeout: None,
+        }
+    }
+}
+
+/// Decode the request in the layout of the ABI version of the extension
+fn decode_request(bytes: &[u8], abi_version: u32) -> Result<Request, transport::Error> {
+    match abi_version {
+        1 => transport::decode::<RequestV1>(bytes).map(Into::into),
+        _ => transport::decode(bytes),
+    }
+}
+
+/// Decode the batch of requests in the layout of the ABI version of the extension
+fn decode_requests(bytes: &[u8], abi_version: u32) -> Result<Vec<Request>, transport::Error> {
+    match abi_version {
+        1 => transport::decode::<Vec<RequestV1>>(bytes)
+            .map(|requests| requests.into_iter().map(Into::into).collect()),
+        _ => transport::decode(bytes),
+    }
+}
+
+/// Read and decode the request written by the guest
+///
+/// Out of bounds memory access is returned as an error of the host call,
+/// while a request that cannot be decoded is answered with a [RequestError].
+pub(crate) fn read_request<D>(
+    caller: &mut Caller<'_, State<D>>,
+    ptr: i32,
+    len: i32,
+    memory: &Memory,
+) -> error::Result<Result<Request, RequestError>> {
+    let abi_version = caller.data().abi_version;
+    let request_data = read_bytes_with_len(caller, memory, ptr, len as u32 as usize)?;
+    let request_data = decode_request(request_data, abi_version).map_err(|e| RequestError {
+        kind: RequestErrorKind::Serial,
+        url: None,
+        message: e.to_string(),
+    });
     debug!("Sending http request: {request_data:?}.");
-    request_data
+    Ok(request_data)
+}
+
+/// Read and decode the batch of requests written by the guest
+///
+/// A batch that cannot be decoded is answered with a single [RequestError].
+pub(crate) fn read_requests<D>(
+    caller: &mut Caller<'_, State<D>>,
+    ptr: i32,
+    len: i32,
+    memory: &Memory,
+) -> error::Result<Result<Vec<Request>, RequestError>> {
+    let abi_version = caller.data().abi_version;
+    let bytes = read_bytes_with_len(caller, memory, ptr, len as u32 as usize)?;
+    let requests = decode_requests(bytes, abi_version).map_err(|e| RequestError {
+        kind: RequestErrorKind::Serial,
+        url: None,
+        message: e.to_string(),
+    });
+    Ok(requests)
+}
+
+/// Encode the responses of a batch and write them into the guest memory
+pub(crate) async fn write_responses<D: Send>(
+    caller: &mut Caller<'_, D>,
+    memory: &Memory,
+    responses: &[Result<Response, RequestError>],
+) -> error::Result<i32> {
+    let bytes = transport::encode(responses).map_err(|_| Error::SerializeError)?;
+    write_bytes(caller, memory, &bytes).await
 }
 
-pub async fn send_request_reqwest<'a, D>(
+/// Encode the response and write it into the guest memory
+pub(crate) async fn write_response<D: Send>(
+    caller: &mut Caller<'_, D>,
+    memory: &Memory,
+    response: &Result<Response, RequestError>,
+) -> error::Result<i32> {
+    let bytes = transport::encode(response).map_err(|_| Error::SerializeError)?;
+    write_bytes(caller, memory, &bytes).await
+}
+
+/// Apply every field of [Request] to a reqwest request
+///
+/// The timeout of the request is capped at `max_timeout`, the one of the client.
+pub fn build_request(
     client: &reqwest::Client,
     request_data: Request,
-) -> Result<reqwest::Response, reqwest::Error> {
-    trace!("executing exposed function 'ext_send_request'");
-
+    max_timeout: Option<Duration>,
+) -> reqwest::RequestBuilder {
     let mut request = client.request(request_data.method.into(), &request_data.url);
+
+    if let Some(params) = &request_data.params {
+        request = request.query(params);
+    }
+
+    if let Some(timeout) = request_data.timeout {
+        let timeout = Duration::from_millis(timeout);
+        request = request.timeout(max_timeout.map_or(timeout, |max| timeout.min(max)));
+    }
+
+    if let Some(headers) = request_data.headers {
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+    }
+
     if let Some(body) = request_data.data {
         match body {
             Body::Form(data) => {
@@ -56,31 +415,302 @@ pub async fn send_request_reqwest<'a, D>(
                 }
                 request = request.multipart(multipart);
             }
+            Body::UrlEncoded(data) => {
+                request = request.form(&data);
+            }
+            Body::Json(data) => {
+                request = request.header(CONTENT_TYPE, "application/json").body(data);
+            }
+            Body::Bytes { content_type, data } => {
+                request = request.header(CONTENT_TYPE, content_type).body(data);
+            }
         };
     }
 
-    request.send().await
+    request
 }
 
+/// Convert the reqwest response, refusing bodies larger than `max_size` bytes
 pub async fn parse_response(
     response: reqwest::Result<reqwest::Response>,
+    max_size: Option<usize>,
 ) -> Result<Response, RequestError> {
-    let response = response?;
-    let header_map = response
-        .headers()
-        .into_iter()
-        .map(|(n, v)| (n.to_string(), v.to_str().unwrap_or_default().to_string()))
-        .collect::<std::collections::HashMap<_, _>>();
-
-    let headers = serde_json::to_string(&header_map).map_err(|_| RequestError {
-        kind: RequestErrorKind::Serial,
-        url: Some(response.url().as_str().to_string()),
-        message: String::from("failed to serialize response"),
-    })?;
+    let mut response = match response {
+        Ok(response) => response,
+        Err(e) => match network::private_address(&e) {
+            Some(private) => {
+                warn!("blocked request: {private}");
+                return Err(RequestError {
+                    kind: RequestErrorKind::Blocked,
+                    url: e.url().map(|url| url.to_string()),
+                    message: private.to_string(),
+                });
+            }
+            None => return Err(e.into()),
+        },
+    };
+    let url = response.url().as_str().to_string();
+
+    let too_large = |max_size: usize| RequestError {
+        kind: RequestErrorKind::TooLarge,
+        url: Some(url.clone()),
+        message: format!("the response body is larger than {max_size} bytes"),
+    };
+
+    if let (Some(max_size), Some(len)) = (max_size, response.content_length()) {
+        if len > max_size as u64 {
+            return Err(too_large(max_size));
+        }
+    }
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in response.headers() {
+        match value.to_str() {
+            Ok(value) => headers.append(name, value),
+            Err(_) => warn!("skipped non-ascii value of response header '{name}'"),
+        }
+    }
+
+    let mut body = Some(Vec::new());
+    while let Some(data) = body.as_mut() {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                data.extend_from_slice(&chunk);
+                if let Some(max_size) = max_size.filter(|max_size| data.len() > *max_size) {
+                    return Err(too_large(max_size));
+                }
+            }
+            Ok(None) => break,
+            Err(_) => body = None,
+        }
+    }
 
     Ok(Response {
         status: response.status().as_u16() as usize,
-        body: response.bytes().await.map(|data| data.to_vec()).ok(),
-        headers: Some(headers),
+        url,
+        headers,
+        body,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use quelle_core::prelude::Method;
+    use reqwest::header::{CONTENT_TYPE, REFERER};
+    use serde::Serialize;
+
+    use crate::client::HttpConfig;
+
+    use super::*;
+
+    #[test]
+    fn should_apply_params_and_headers() {
+        let client = HttpConfig::default().client().unwrap();
+        let request = Request::get(String::from("https://example.com/search"))
+            .param("q", "a b")
+            .param("page", "2")
+            .header("Referer", "https://example.com");
+
+        let request = client.build(request).build().unwrap();
+
+        assert_eq!(request.url().query(), Some("q=a+b&page=2"));
+        assert_eq!(request.headers()[REFERER], "https://example.com");
+    }
+
+    #[test]
+    fn should_encode_url_encoded_body() {
+        let client = HttpConfig::default().client().unwrap();
+        let form = HashMap::from([(String::from("action"), String::from("list"))]);
+        let request =
+            Request::new(Method::Post, String::from("https://example.com")).url_encoded(form);
+
+        let request = client.build(request).build().unwrap();
+
+        assert_eq!(
+            request.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()),
+            Some("action=list".as_bytes())
+        );
+    }
+
+    #[test]
+    fn should_decode_requests_of_older_extensions() {
+        #[derive(Serialize)]
+        struct RequestV1<'a> {
+            method: Method,
+            url: &'a str,
+            params: Option<Vec<(String, String)>>,
+            data: Option<Body>,
+            headers: Option<Vec<(String, String)>>,
+        }
+
+        let old = RequestV1 {
+            method: Method::Get,
+            url: "https://example.com",
+            params: None,
+            data: None,
+            headers: None,
+        };
+        let bytes = transport::encode(&[&old, &old][..]).unwrap();
+        let requests = decode_requests(&bytes, 1).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.timeout.is_none()));
+
+        let request = Request::get(String::from("https://example.com"))
+            .timeout(std::time::Duration::from_secs(5));
+        let bytes = transport::encode(&request).unwrap();
+        assert_eq!(decode_request(&bytes, 2).unwrap().timeout, Some(5_000));
+    }
+
+    #[test]
+    fn should_apply_request_timeout() {
+        let config = |timeout| HttpConfig {
+            timeout,
+            ..Default::default()
+        };
+        let request = Request::get(String::from("https://example.com"))
+            .timeout(std::time::Duration::from_millis(1500));
+
+        let capped = config(Some(1000)).client().unwrap().build(request.clone());
+        let request = config(None)
+            .client()
+            .unwrap()
+            .build(request)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.timeout(), Some(&Duration::from_millis(1500)));
+        assert_eq!(
+            capped.build().unwrap().timeout(),
+            Some(&Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn should_send_bytes_with_content_type() {
+        let client = HttpConfig::default().client().unwrap();
+        let request = Request::post(String::from("https://example.com"))
+            .bytes("text/plain", b"hello".to_vec());
+
+        let request = client.build(request).build().unwrap();
+
+        assert_eq!(request.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()),
+            Some("hello".as_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_send_batch_concurrently_in_order() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        use crate::{cookie::CookieJar, network::NetworkPolicy, RuntimeBuilder};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (server_active, server_peak) = (active.clone(), peak.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let (active, peak) = (server_active.clone(), server_peak.clone());
+                tokio::spawn(async move {
+                    let mut buf = [0; 1024];
+                    let len = socket.read(&mut buf).await.unwrap();
+                    let head = String::from_utf8_lossy(&buf[..len]).to_string();
+                    let path = head.split(' ').nth(1).unwrap().to_string();
+
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{path}",
+                        path.len()
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let state = RuntimeBuilder::default()
+            .network(NetworkPolicy::default().allow_any_host().allow_private())
+            .max_concurrent_requests(2)
+            .http_client(HttpConfig::default().client().unwrap())
+            .state((), CookieJar::default());
+        let requests = (1..=5)
+            .map(|page| Request::get(format!("http://{addr}/page/{page}")))
+            .collect();
+
+        let responses = send_all(&state, requests).await.unwrap();
+        let bodies = responses
+            .into_iter()
+            .map(|response| String::from_utf8(response.unwrap().body.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            bodies,
+            ["/page/1", "/page/2", "/page/3", "/page/4", "/page/5"]
+        );
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    fn redirect_response(status: usize, location: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        headers.append("Location", location);
+        Response {
+            status,
+            url: String::from("https://example.com/login"),
+            body: None,
+            headers,
+        }
+    }
+
+    #[test]
+    fn should_follow_redirect_location() {
+        let request = Request::get(String::from("https://example.com/login"))
+            .param("next", "home")
+            .header("Authorization", "secret");
+
+        let next = redirect(&request, &redirect_response(302, "/home")).unwrap();
+        assert_eq!(next.url, "https://example.com/home");
+        assert!(next.params.is_none());
+        assert_eq!(next.headers.as_ref().map(Vec::len), Some(1));
+
+        let next = redirect(&request, &redirect_response(301, "https://other.com/")).unwrap();
+        assert_eq!(next.url, "https://other.com/");
+        assert_eq!(next.headers.as_ref().map(Vec::len), Some(0));
+
+        assert!(redirect(&request, &redirect_response(200, "/home")).is_none());
+    }
+
+    #[test]
+    fn should_turn_redirected_post_into_get() {
+        let request =
+            Request::post(String::from("https://example.com/login")).bytes("text/plain", vec![1]);
+
+        let next = redirect(&request, &redirect_response(303, "/home")).unwrap();
+        assert!(matches!(next.method, Method::Get));
+        assert!(next.data.is_none());
+
+        let next = redirect(&request, &redirect_response(307, "/home")).unwrap();
+        assert!(matches!(next.method, Method::Post));
+        assert!(next.data.is_some());
+    }
+}
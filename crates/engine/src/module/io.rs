@@ -1,28 +1,43 @@
-use log::trace;
+use log::{trace, warn};
 use wasmtime::Caller;
 
-use crate::module::utils::read_str_with_len;
+use crate::{
+    data::State,
+    module::utils::{get_memory, read_str_with_len},
+    output::Stream,
+};
 
-pub fn print<D>(mut caller: Caller<'_, D>, ptr: i32, len: u32) {
+pub fn print<D>(caller: Caller<'_, State<D>>, ptr: i32, len: u32) {
     trace!("executing exposed function 'print'");
-
-    let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-    let string = read_str_with_len(&mut caller, &memory, ptr, len as usize);
-    print!("{string}");
+    write(caller, ptr, len, Stream::Stdout, "");
 }
 
-pub fn eprint<D>(mut caller: Caller<'_, D>, ptr: i32, len: u32) {
+pub fn eprint<D>(caller: Caller<'_, State<D>>, ptr: i32, len: u32) {
     trace!("executing exposed function 'eprint'");
-
-    let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-    let string = read_str_with_len(&mut caller, &memory, ptr, len as usize);
-    eprint!("{string}");
+    write(caller, ptr, len, Stream::Stderr, "");
 }
 
-pub fn trace<D>(mut caller: Caller<'_, D>, ptr: i32, len: u32) {
+pub fn trace<D>(caller: Caller<'_, State<D>>, ptr: i32, len: u32) {
     trace!("executing exposed function 'trace'");
+    write(caller, ptr, len, Stream::Stderr, "\n");
+}
+
+/// Write the guest string to the [Output](crate::output::Output) of the runtime
+fn write<D>(mut caller: Caller<'_, State<D>>, ptr: i32, len: u32, stream: Stream, end: &str) {
+    let output = caller.data().output().clone();
+
+    match read_guest_str(&mut caller, ptr, len) {
+        Ok(string) if end.is_empty() => output.write(stream, string),
+        Ok(string) => output.write(stream, &format!("{string}{end}")),
+        Err(e) => warn!("{e}"),
+    }
+}
 
-    let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-    let string = read_str_with_len(&mut caller, &memory, ptr, len as usize);
-    eprintln!("{string}");
+fn read_guest_str<'c, D>(
+    caller: &'c mut Caller<'_, D>,
+    ptr: i32,
+    len: u32,
+) -> crate::error::Result<&'c str> {
+    let memory = get_memory(caller)?;
+    read_str_with_len(caller, &memory, ptr, len as usize)
 }
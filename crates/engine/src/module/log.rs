@@ -1,12 +1,27 @@
-use log::warn;
+use log::{warn, Record};
 use quelle_core::prelude::LogEvent;
 use wasmtime::Caller;
 
-use super::utils::read_bytes_with_len;
+use super::utils::{get_memory, read_bytes_with_len};
+use crate::data::State;
 
-pub fn event<D>(mut caller: Caller<'_, D>, ptr: i32, len: i32) {
-    let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
-    let bytes = read_bytes_with_len(&mut caller, &memory, ptr, len as usize);
+/// The target of the logs sent before the id of the extension is known
+const DEFAULT_TARGET: &str = "extension";
+
+/// Forward a log event of the extension to the logger of the host
+///
+/// The event is logged with the id of the extension as target and keeps
+/// the location it was logged at within the extension.
+pub fn event<D>(mut caller: Caller<'_, State<D>>, ptr: i32, len: i32) {
+    let bytes = match get_memory(&mut caller)
+        .and_then(|memory| read_bytes_with_len(&caller, &memory, ptr, len as usize))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("{e}");
+            return;
+        }
+    };
 
     let event = match serde_json::from_slice::<LogEvent>(bytes) {
         Ok(v) => v,
@@ -16,5 +31,27 @@ pub fn event<D>(mut caller: Caller<'_, D>, ptr: i32, len: i32) {
         }
     };
 
-    println!("{} - {}", event.level, event.args);
+    forward(caller.data(), &event);
+}
+
+/// Log the event of the extension with the logger of the host
+pub(crate) fn forward<D>(state: &State<D>, event: &LogEvent) {
+    if state
+        .level_filter
+        .is_some_and(|filter| event.level > filter)
+    {
+        return;
+    }
+
+    let target = state.extension_id().unwrap_or(DEFAULT_TARGET);
+    log::logger().log(
+        &Record::builder()
+            .level(event.level)
+            .target(target)
+            .module_path(event.module_path)
+            .file(event.file)
+            .line(event.line)
+            .args(format_args!("{}", event.args))
+            .build(),
+    );
 }
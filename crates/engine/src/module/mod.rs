@@ -2,3 +2,5 @@ pub mod http;
 pub mod io;
 pub mod utils;
 pub mod log;
+pub mod storage;
+pub mod time;
@@ -0,0 +1,69 @@
+use std::future::Future;
+
+use log::trace;
+use quelle_core::{prelude::StorageError, transport};
+use serde::Serialize;
+use wasmtime::{Caller, Memory};
+
+use crate::{
+    data::State,
+    error::{self, Error},
+    module::utils::{get_memory, read_bytes_with_len, read_str_with_len, write_bytes},
+    storage::Namespace,
+};
+
+type HostFuture<'a> = Box<dyn Future<Output = anyhow::Result<i32>> + Send + 'a>;
+
+/// Read the value of the key as an encoded `Result<Option<String>, StorageError>`
+pub fn get<'a, D: Send>(mut caller: Caller<'a, State<D>>, ptr: i32, len: i32) -> HostFuture<'a> {
+    trace!("executing exposed function 'storage_get'");
+    Box::new(async move {
+        let memory = get_memory(&mut caller)?;
+        let key = read_str_with_len(&caller, &memory, ptr, len as u32 as usize)?;
+        let result = namespace(caller.data()).map(|namespace| namespace.get(key));
+        Ok(write_result(&mut caller, &memory, &result).await?)
+    })
+}
+
+/// Store the encoded `(key, value)` pair, returning an encoded `Result<(), StorageError>`
+pub fn set<'a, D: Send>(mut caller: Caller<'a, State<D>>, ptr: i32, len: i32) -> HostFuture<'a> {
+    trace!("executing exposed function 'storage_set'");
+    Box::new(async move {
+        let memory = get_memory(&mut caller)?;
+        let bytes = read_bytes_with_len(&caller, &memory, ptr, len as u32 as usize)?;
+        let result = transport::decode::<(&str, &str)>(bytes)
+            .map_err(|_| StorageError::Serial)
+            .and_then(|(key, value)| namespace(caller.data())?.set(key, value));
+        Ok(write_result(&mut caller, &memory, &result).await?)
+    })
+}
+
+/// Remove the value of the key, returning an encoded `Result<(), StorageError>`
+pub fn delete<'a, D: Send>(mut caller: Caller<'a, State<D>>, ptr: i32, len: i32) -> HostFuture<'a> {
+    trace!("executing exposed function 'storage_delete'");
+    Box::new(async move {
+        let memory = get_memory(&mut caller)?;
+        let key = read_str_with_len(&caller, &memory, ptr, len as u32 as usize)?;
+        let result = namespace(caller.data()).and_then(|namespace| namespace.delete(key));
+        Ok(write_result(&mut caller, &memory, &result).await?)
+    })
+}
+
+/// The values of the extension, known once its meta was read
+pub(crate) fn namespace<D>(state: &State<D>) -> Result<Namespace, StorageError> {
+    match state.extension_id() {
+        Some(id) => Ok(state.storage().namespace(id)),
+        None => Err(StorageError::Unavailable(String::from(
+            "the id of the extension is unknown until its meta is read",
+        ))),
+    }
+}
+
+async fn write_result<D: Send, T: Serialize>(
+    caller: &mut Caller<'_, D>,
+    memory: &Memory,
+    result: &Result<T, StorageError>,
+) -> error::Result<i32> {
+    let bytes = transport::encode(result).map_err(|_| Error::SerializeError)?;
+    write_bytes(caller, memory, &bytes).await
+}
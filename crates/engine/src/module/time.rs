@@ -0,0 +1,70 @@
+use log::trace;
+use wasmtime::Caller;
+
+use crate::data::State;
+
+/// The current time as milliseconds since the unix epoch in UTC
+pub fn now<D>(caller: Caller<'_, State<D>>) -> i64 {
+    trace!("executing exposed function 'time_now'");
+    caller.data().clock().now().timestamp_millis()
+}
+
+/// The offset of the timezone of the user in seconds east of UTC
+pub fn utc_offset<D>(caller: Caller<'_, State<D>>) -> i32 {
+    trace!("executing exposed function 'time_utc_offset'");
+    caller.data().clock().utc_offset().local_minus_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, TimeZone, Utc};
+    use wasmtime::{Engine, Linker, Module, Store};
+
+    use super::*;
+    use crate::{clock::Clock, cookie::CookieJar, RuntimeBuilder};
+
+    #[test]
+    fn should_read_the_injected_clock() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap();
+        let offset = FixedOffset::west_opt(5 * 3600).unwrap();
+        let state = RuntimeBuilder::default()
+            .clock(Clock::fixed(time).offset(offset))
+            .state((), CookieJar::default());
+
+        let engine = Engine::default();
+        let module = Module::new(
+            &engine,
+            r#"
+                (module
+                  (import "env" "time_now" (func $now (result i64)))
+                  (import "env" "time_utc_offset" (func $offset (result i32)))
+                  (func (export "now") (result i64) call $now)
+                  (func (export "offset") (result i32) call $offset))
+            "#,
+        )
+        .unwrap();
+
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("env", "time_now", now::<()>).unwrap();
+        linker
+            .func_wrap("env", "time_utc_offset", utc_offset::<()>)
+            .unwrap();
+
+        let mut store = Store::new(&engine, state);
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+
+        let millis = instance
+            .get_typed_func::<(), i64>(&mut store, "now")
+            .unwrap()
+            .call(&mut store, ())
+            .unwrap();
+        assert_eq!(millis, time.timestamp_millis());
+
+        let seconds = instance
+            .get_typed_func::<(), i32>(&mut store, "offset")
+            .unwrap()
+            .call(&mut store, ())
+            .unwrap();
+        assert_eq!(seconds, -5 * 3600);
+    }
+}
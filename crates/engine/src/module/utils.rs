@@ -1,95 +1,121 @@
-use std::slice;
-
 use log::{debug, info};
-use wasmtime::{AsContext, AsContextMut, Caller, Memory};
+use wasmtime::{AsContext, AsContextMut, Caller, Extern, Func, Memory};
+
+use crate::error::{self, Error};
 
-pub async fn read_str<'c, 'm, D: Send>(
+pub async fn read_str<'c, D: Send>(
     caller: &'c mut Caller<'_, D>,
-    memory: &'m Memory,
+    memory: &Memory,
     ptr: i32,
-) -> &'m str {
-    let len = stack_pop(caller).await as usize;
+) -> error::Result<&'c str> {
+    let len = stack_pop(caller).await? as usize;
     debug!("retrieved byte length from stack: {len}");
     read_str_with_len(caller, memory, ptr, len)
 }
 
-pub fn read_str_with_len<'c, 'm, D>(
-    caller: &'c mut Caller<'_, D>,
-    memory: &'m Memory,
+pub fn read_str_with_len<'c, D>(
+    caller: &'c Caller<'_, D>,
+    memory: &Memory,
     ptr: i32,
     len: usize,
-) -> &'m str {
+) -> error::Result<&'c str> {
     info!("reading string from wasm memory of len: {len}");
 
-    unsafe {
-        let ptr = memory.data_ptr(&caller).offset(ptr as isize);
-        let bytes = slice::from_raw_parts(ptr, len);
-        std::str::from_utf8(bytes).unwrap()
-    }
+    let bytes = read_bytes_with_len(caller, memory, ptr, len)?;
+    std::str::from_utf8(bytes).map_err(Into::into)
 }
 
-pub fn read_bytes_with_len<'c, 'm, D>(
-    caller: &'c mut Caller<'_, D>,
-    memory: &'m Memory,
+pub fn read_bytes_with_len<'c, D>(
+    caller: &'c Caller<'_, D>,
+    memory: &Memory,
     ptr: i32,
     len: usize,
-) -> &'m [u8] {
+) -> error::Result<&'c [u8]> {
     info!("reading bytes from wasm memory of len: {len}");
+    checked_slice(memory.data(caller), ptr, len)
+}
 
-    unsafe {
-        let ptr = memory.data_ptr(&caller).offset(ptr as isize);
-        let bytes = slice::from_raw_parts(ptr, len);
-        bytes
-    }
+/// Get the bytes in `ptr..ptr + len` if the whole range is within the memory
+///
+/// Guest pointers are unsigned, so negative values address the upper half
+/// of the memory.
+pub fn checked_slice(data: &[u8], ptr: i32, len: usize) -> error::Result<&[u8]> {
+    let start = ptr as u32 as usize;
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or(Error::MemoryAccessError)
 }
 
-pub async fn write_str<'c, 'm, D: Send>(
-    caller: &'c mut Caller<'_, D>,
-    memory: &'m Memory,
+pub async fn write_str<D: Send>(
+    caller: &mut Caller<'_, D>,
+    memory: &Memory,
     value: &str,
-) -> i32 {
-    let alloc_func = caller.get_export("alloc").unwrap().into_func().unwrap();
+) -> error::Result<i32> {
+    write_bytes(caller, memory, value.as_bytes()).await
+}
 
-    let ptr = alloc_func
-        .typed::<i32, i32>(caller.as_context())
-        .unwrap()
+pub async fn write_bytes<D: Send>(
+    caller: &mut Caller<'_, D>,
+    memory: &Memory,
+    value: &[u8],
+) -> error::Result<i32> {
+    let ptr = get_func(caller, "alloc")?
+        .typed::<i32, i32>(caller.as_context())?
         .call_async(caller.as_context_mut(), value.len() as i32)
-        .await
-        .unwrap();
+        .await?;
 
-    stack_push(caller, value.len() as i32).await;
+    stack_push(caller, value.len() as i32).await?;
 
     memory
-        .write(caller.as_context_mut(), ptr as usize, value.as_bytes())
-        .unwrap();
+        .write(caller.as_context_mut(), ptr as u32 as usize, value)
+        .map_err(|_| Error::MemoryAccessError)?;
 
-    ptr
+    Ok(ptr)
 }
 
-pub async fn stack_push<'c, 'm, D: Send>(caller: &'c mut Caller<'_, D>, value: i32) {
-    let push_fn = caller
-        .get_export("stack_push")
-        .unwrap()
-        .into_func()
-        .unwrap();
-
-    push_fn
-        .typed::<i32, ()>(&caller)
-        .unwrap()
+pub async fn stack_push<D: Send>(caller: &mut Caller<'_, D>, value: i32) -> error::Result<()> {
+    get_func(caller, "stack_push")?
+        .typed::<i32, ()>(&caller)?
         .call_async(caller, value)
         .await
-        .unwrap();
+        .map_err(Into::into)
 }
 
-pub async fn stack_pop<'c, 'm, D: Send>(caller: &'c mut Caller<'_, D>) -> i32 {
-    let pop_fn = caller.get_export("stack_pop").unwrap().into_func().unwrap();
-
-    let value = pop_fn
-        .typed::<(), i32>(&caller)
-        .unwrap()
+pub async fn stack_pop<D: Send>(caller: &mut Caller<'_, D>) -> error::Result<i32> {
+    get_func(caller, "stack_pop")?
+        .typed::<(), i32>(&caller)?
         .call_async(caller, ())
         .await
-        .unwrap();
+        .map_err(Into::into)
+}
+
+pub fn get_memory<D>(caller: &mut Caller<'_, D>) -> error::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::MissingExport(String::from("memory")))
+}
 
-    value
+pub fn get_func<D>(caller: &mut Caller<'_, D>, name: &str) -> error::Result<Func> {
+    caller
+        .get_export(name)
+        .and_then(Extern::into_func)
+        .ok_or_else(|| Error::MissingExport(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reject_out_of_bounds_slices() {
+        let data = [0u8; 16];
+
+        assert_eq!(checked_slice(&data, 0, 16).unwrap().len(), 16);
+        assert_eq!(checked_slice(&data, 16, 0).unwrap().len(), 0);
+        assert!(checked_slice(&data, 8, 9).is_err());
+        assert!(checked_slice(&data, -1, 1).is_err());
+        assert!(checked_slice(&data, 8, usize::MAX).is_err());
+    }
 }
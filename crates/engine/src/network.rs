@@ -0,0 +1,342 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
+};
+
+use log::warn;
+use quelle_core::prelude::{Meta, RequestError, RequestErrorKind};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use url::{Host, Url};
+
+/// The network rules the requests of an extension have to follow
+///
+/// By default an extension may only reach the hosts of its [Meta::base_urls]
+/// and [Meta::extra_hosts], together with their subdomains, and never loopback
+/// or private addresses. The hosts are known once the meta of the extension
+/// was read, until then every request is refused. Clones share the hosts, so
+/// the rules also hold across the runtimes of a [RuntimePool](crate::pool::RuntimePool).
+///
+/// Images fetched with [Runtime::fetch_image](crate::Runtime::fetch_image) may
+/// come from any host that is not private.
+///
+/// Only literal addresses and `localhost` are checked against the private ranges
+/// here. Domains are checked when connecting by the [PublicResolver] of the
+/// client, as a domain may resolve to another address by then.
+#[derive(Clone)]
+pub struct NetworkPolicy {
+    allow_any_host: bool,
+    allow_private: bool,
+    configured: Vec<String>,
+    hints: Arc<Mutex<Option<HashSet<String>>>>,
+    max_response_size: Option<usize>,
+    max_redirects: usize,
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self {
+            allow_any_host: false,
+            allow_private: false,
+            configured: Vec::new(),
+            hints: Default::default(),
+            max_response_size: Some(64 * 1024 * 1024),
+            max_redirects: 10,
+        }
+    }
+}
+
+impl NetworkPolicy {
+    /// Allow the host and its subdomains in addition to the hosts of the extension
+    pub fn allow_host(mut self, host: &str) -> Self {
+        self.configured.push(normalize_host(host));
+        self
+    }
+
+    /// Allow requests to every public host
+    pub fn allow_any_host(mut self) -> Self {
+        self.allow_any_host = true;
+        self
+    }
+
+    /// Allow requests to loopback and private addresses
+    pub fn allow_private(mut self) -> Self {
+        self.allow_private = true;
+        self
+    }
+
+    /// Refuse response bodies larger than the size in bytes
+    pub fn max_response_size(mut self, max_response_size: Option<usize>) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Refuse to follow more than the given number of redirects
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn response_size_limit(&self) -> Option<usize> {
+        self.max_response_size
+    }
+
+    pub fn redirect_limit(&self) -> usize {
+        self.max_redirects
+    }
+
+    /// Allow the hosts declared by the extension
+    pub fn hint(&self, meta: &Meta) {
+        let hosts = meta
+            .base_urls
+            .iter()
+            .filter_map(|url| Url::parse(url).ok()?.host_str().map(normalize_host))
+            .chain(meta.extra_hosts.iter().map(|host| normalize_host(host)))
+            .collect();
+
+        *self.hints.lock().unwrap() = Some(hosts);
+    }
+
+    /// Whether the extension may send a request to the url
+    pub fn check(&self, url: &str) -> Result<(), RequestError> {
+        let blocked = |message: String| {
+            warn!("blocked request to '{url}': {message}");
+            Err(RequestError {
+                kind: RequestErrorKind::Blocked,
+                url: Some(url.to_string()),
+                message,
+            })
+        };
+
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(e) => return blocked(format!("invalid url: {e}")),
+        };
+
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return blocked(format!("the '{}' scheme is not allowed", parsed.scheme()));
+        }
+
+        let host = match parsed.host() {
+            Some(Host::Domain(domain)) => domain.to_ascii_lowercase(),
+            Some(Host::Ipv4(ip)) => ip.to_string(),
+            Some(Host::Ipv6(ip)) => ip.to_string(),
+            None => return blocked(String::from("the url has no host")),
+        };
+
+        if !self.allow_any_host && !self.is_allowed(&host) {
+            if self.hints.lock().unwrap().is_none() {
+                return blocked(String::from(
+                    "the hosts of the extension are unknown until its meta is read",
+                ));
+            }
+            return blocked(format!("'{host}' is not an allowed host of the extension"));
+        }
+
+        if !self.allow_private {
+            let private = match parsed.host() {
+                Some(Host::Ipv4(ip)) => is_private(IpAddr::V4(ip)),
+                Some(Host::Ipv6(ip)) => is_private(IpAddr::V6(ip)),
+                _ => host == "localhost" || host.ends_with(".localhost"),
+            };
+
+            if private {
+                return blocked(format!("'{host}' is a loopback or private address"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_allowed(&self, host: &str) -> bool {
+        let hints = self.hints.lock().unwrap();
+        let hints = hints.iter().flatten();
+
+        self.configured.iter().chain(hints).any(|allowed| {
+            host == allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+}
+
+/// Allow `www.example.com` to also match `example.com` and its other subdomains
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_start_matches("*.").to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(host) => host.to_string(),
+        None => host,
+    }
+}
+
+/// Resolves domains for the client, dropping loopback and private addresses
+///
+/// Connections only go to the addresses returned here, so a public name can not
+/// point into the user's network, even when it resolves to another address than
+/// the one seen before the request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let domain = name.as_str();
+            let addrs = tokio::net::lookup_host((domain, 0))
+                .await?
+                .filter(|addr| !is_private(addr.ip()))
+                .collect::<Vec<_>>();
+
+            if addrs.is_empty() {
+                return Err(PrivateAddress(domain.to_string()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The domain only resolved to loopback or private addresses
+#[derive(Debug)]
+pub struct PrivateAddress(pub String);
+
+impl Display for PrivateAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is a loopback or private address", self.0)
+    }
+}
+
+impl std::error::Error for PrivateAddress {}
+
+/// The [PrivateAddress] the request failed with, if any
+pub(crate) fn private_address(error: &reqwest::Error) -> Option<&PrivateAddress> {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(private) = error.downcast_ref::<PrivateAddress>() {
+            return Some(private);
+        }
+        source = error.source();
+    }
+    None
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_v4(ip),
+            None => is_private_v6(ip),
+        },
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        // "This network", including the unspecified address
+        || a == 0
+        // Shared address space used by carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // Benchmarking networks
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved for future use, including the broadcast address
+        || a >= 240
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local addresses
+        || (first & 0xfe00) == 0xfc00
+        // Link local addresses
+        || (first & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> Meta {
+        Meta {
+            base_urls: vec![String::from("https://www.example.com")],
+            extra_hosts: vec![String::from("cdn.images.net")],
+            ..Default::default()
+        }
+    }
+
+    fn kind(policy: &NetworkPolicy, url: &str) -> Option<RequestErrorKind> {
+        policy.check(url).err().map(|e| e.kind)
+    }
+
+    #[test]
+    fn should_only_allow_hosts_of_the_extension() {
+        let policy = NetworkPolicy::default();
+        assert!(matches!(
+            kind(&policy, "https://www.example.com"),
+            Some(RequestErrorKind::Blocked)
+        ));
+
+        policy.hint(&meta());
+        assert!(kind(&policy, "https://www.example.com/novel").is_none());
+        assert!(kind(&policy, "https://example.com").is_none());
+        assert!(kind(&policy, "https://static.example.com").is_none());
+        assert!(kind(&policy, "https://cdn.images.net/cover.jpg").is_none());
+        assert!(matches!(
+            kind(&policy, "https://notexample.com"),
+            Some(RequestErrorKind::Blocked)
+        ));
+        assert!(matches!(
+            kind(&policy, "https://images.net"),
+            Some(RequestErrorKind::Blocked)
+        ));
+    }
+
+    #[test]
+    fn should_block_private_addresses_and_other_schemes() {
+        let policy = NetworkPolicy::default().allow_any_host();
+
+        for url in [
+            "file:///etc/passwd",
+            "ftp://example.com",
+            "http://localhost:8080",
+            "http://api.localhost",
+            "http://127.0.0.1",
+            "http://10.0.0.1",
+            "http://192.168.1.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1",
+            "http://[::1]",
+            "http://[fd00::1]",
+            "http://[::ffff:192.168.1.1]",
+            "http://0.0.0.0",
+            "http://0.1.2.3",
+            "http://198.18.0.1",
+            "http://198.19.255.255",
+            "http://224.0.0.1",
+            "http://240.0.0.1",
+            "http://255.255.255.255",
+        ] {
+            assert!(
+                matches!(kind(&policy, url), Some(RequestErrorKind::Blocked)),
+                "{url} was not blocked"
+            );
+        }
+
+        assert!(kind(&policy, "http://8.8.8.8").is_none());
+        assert!(kind(&policy, "http://198.20.0.1").is_none());
+
+        let policy = policy.allow_private();
+        assert!(kind(&policy, "http://192.168.1.1").is_none());
+    }
+
+    #[test]
+    fn should_allow_configured_hosts() {
+        let policy = NetworkPolicy::default().allow_host("api.other.org");
+        assert!(kind(&policy, "https://api.other.org").is_none());
+        assert!(kind(&policy, "https://other.org").is_some());
+    }
+}
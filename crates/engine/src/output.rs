@@ -0,0 +1,114 @@
+use std::{
+    fmt::Display,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// The stream an extension printed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Display for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        };
+
+        write!(f, "{value}")
+    }
+}
+
+type Sink = dyn Fn(Stream, &str) + Send + Sync;
+
+/// Where the text an extension prints to its stdout and stderr goes
+///
+/// Prints to the stdout and stderr of the host by default.
+#[derive(Clone)]
+pub struct Output {
+    sink: Arc<Sink>,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::new(|stream, text| {
+            // The extension has no way to handle a failed write
+            let _ = match stream {
+                Stream::Stdout => std::io::stdout().write_all(text.as_bytes()),
+                Stream::Stderr => std::io::stderr().write_all(text.as_bytes()),
+            };
+        })
+    }
+}
+
+impl Output {
+    /// Pass the printed text to the sink
+    pub fn new<F>(sink: F) -> Self
+    where
+        F: Fn(Stream, &str) + Send + Sync + 'static,
+    {
+        Self {
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Discard everything the extension prints
+    pub fn discard() -> Self {
+        Self::new(|_, _| {})
+    }
+
+    pub fn write(&self, stream: Stream, text: &str) {
+        (self.sink)(stream, text)
+    }
+}
+
+/// Collects the text printed by an extension
+///
+/// Clones share the same buffers.
+#[derive(Clone, Default)]
+pub struct OutputBuffer {
+    stdout: Arc<Mutex<String>>,
+    stderr: Arc<Mutex<String>>,
+}
+
+impl OutputBuffer {
+    /// The output appending to this buffer
+    pub fn output(&self) -> Output {
+        let buffer = self.clone();
+        Output::new(move |stream, text| buffer.buffer(stream).lock().unwrap().push_str(text))
+    }
+
+    /// Take the text printed to the stream so far
+    pub fn take(&self, stream: Stream) -> String {
+        std::mem::take(&mut *self.buffer(stream).lock().unwrap())
+    }
+
+    fn buffer(&self, stream: Stream) -> &Mutex<String> {
+        match stream {
+            Stream::Stdout => &self.stdout,
+            Stream::Stderr => &self.stderr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_collect_each_stream() {
+        let buffer = OutputBuffer::default();
+        let output = buffer.output();
+
+        output.write(Stream::Stdout, "hello ");
+        output.write(Stream::Stderr, "oops");
+        output.write(Stream::Stdout, "world");
+
+        assert_eq!(buffer.take(Stream::Stdout), "hello world");
+        assert_eq!(buffer.take(Stream::Stderr), "oops");
+        assert_eq!(buffer.take(Stream::Stdout), "");
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use quelle_core::prelude::{BasicNovel, Content, ExtensionConfig, Response};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{client::HttpConfig, data::DefaultImpl, error, Runtime, RuntimeBuilder};
+
+/// A fixed set of runtimes created from the same compiled module
+///
+/// Every call takes an idle runtime, waiting until one is returned if all
+/// of them are busy. Cloning the pool is cheap and the clones share the
+/// same runtimes, so it can be handed to different tokio tasks.
+pub struct RuntimePool<D> {
+    inner: Arc<Inner<D>>,
+}
+
+struct Inner<D> {
+    idle: Mutex<Vec<Runtime<D>>>,
+    permits: Semaphore,
+    size: usize,
+}
+
+impl<D> Clone for RuntimePool<D> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl RuntimePool<DefaultImpl> {
+    pub async fn new(path: &Path, size: usize, http: &HttpConfig) -> error::Result<Self> {
+        let data = DefaultImpl::new(http)?;
+        RuntimeBuilder::default()
+            .http_client(data.client.clone())
+            .rate_limiter(http.rate_limiter())
+            .build_pool(path, size, data)
+            .await
+    }
+}
+
+impl<D> RuntimePool<D>
+where
+    D: Send,
+{
+    pub(crate) fn from_runtimes(runtimes: Vec<Runtime<D>>) -> Self {
+        let size = runtimes.len();
+        Self {
+            inner: Arc::new(Inner {
+                idle: Mutex::new(runtimes),
+                permits: Semaphore::new(size),
+                size,
+            }),
+        }
+    }
+
+    /// The number of runtimes in the pool
+    pub fn size(&self) -> usize {
+        self.inner.size
+    }
+
+    /// Wait for an idle runtime, which returns to the pool once dropped
+    pub async fn get(&self) -> PooledRuntime<'_, D> {
+        let permit = self
+            .inner
+            .permits
+            .acquire()
+            .await
+            .expect("the pool semaphore is never closed");
+
+        let runtime = self.inner.idle.lock().unwrap().pop();
+
+        PooledRuntime {
+            runtime,
+            pool: &self.inner,
+            _permit: permit,
+        }
+    }
+
+    /// Call the setup function of every runtime in the pool
+    pub async fn setup(&self, config: &ExtensionConfig) -> error::Result<()> {
+        let mut runtimes = Vec::with_capacity(self.size());
+        for _ in 0..self.size() {
+            runtimes.push(self.get().await);
+        }
+
+        for runtime in runtimes.iter_mut() {
+            runtime.setup(config).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn fetch_chapter_content(&self, url: &str) -> error::Result<Content> {
+        self.get().await.fetch_chapter_content(url).await
+    }
+
+    pub async fn popular(&self, page: i32) -> error::Result<Vec<BasicNovel>> {
+        self.get().await.popular(page).await
+    }
+
+    pub async fn text_search(&self, query: &str, page: i32) -> error::Result<Vec<BasicNovel>> {
+        self.get().await.text_search(query, page).await
+    }
+
+    pub async fn fetch_image(&self, url: &str) -> error::Result<Response> {
+        self.get().await.fetch_image(url).await
+    }
+}
+
+/// A runtime borrowed from a [RuntimePool]
+pub struct PooledRuntime<'a, D> {
+    runtime: Option<Runtime<D>>,
+    pool: &'a Inner<D>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<D> Deref for PooledRuntime<'_, D> {
+    type Target = Runtime<D>;
+
+    fn deref(&self) -> &Self::Target {
+        self.runtime
+            .as_ref()
+            .expect("a permit always has an idle runtime")
+    }
+}
+
+impl<D> DerefMut for PooledRuntime<'_, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.runtime
+            .as_mut()
+            .expect("a permit always has an idle runtime")
+    }
+}
+
+impl<D> Drop for PooledRuntime<'_, D> {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            self.pool.idle.lock().unwrap().push(runtime);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+    use crate::error::Error;
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trap.wat")
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_an_idle_runtime() {
+        let pool = Runtime::builder()
+            .build_pool(&fixture(), 2, ())
+            .await
+            .unwrap();
+
+        let first = pool.get().await;
+        let _second = pool.get().await;
+
+        let third = tokio::time::timeout(Duration::from_millis(10), pool.get()).await;
+        assert!(third.is_err());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(10), pool.get()).await;
+        assert!(third.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_share_runtimes_between_tasks() {
+        let pool = Runtime::builder()
+            .build_pool(&fixture(), 2, ())
+            .await
+            .unwrap();
+        pool.setup(&ExtensionConfig::default()).await.unwrap();
+
+        let tasks = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    let mut runtime = pool.get().await;
+                    runtime.text_search_url("query", 1).await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "");
+        }
+
+        let content = pool.fetch_chapter_content("https://example.com").await;
+        assert!(matches!(content, Err(Error::FailedResultAttempt)));
+    }
+}
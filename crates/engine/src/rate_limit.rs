@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use log::debug;
+use quelle_core::prelude::{Meta, RateLimit};
+use reqwest::Url;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{self, Instant},
+};
+
+/// Schedules the requests sent by extensions according to per-site [RateLimit]s
+///
+/// A site is the registrable domain of a host, so `www.example.com`,
+/// `m.example.com` and `example.com` share one schedule, see [site].
+///
+/// The limit of a site is the stricter of the default limit, the limit
+/// configured for that site, and the hint from the extension's [Meta].
+/// Clones share the same schedule, so the limits also hold across the
+/// runtimes of a [RuntimePool](crate::pool::RuntimePool).
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    default: RateLimit,
+    configured: Mutex<HashMap<String, RateLimit>>,
+    hints: Mutex<HashMap<String, RateLimit>>,
+    sites: Mutex<HashMap<String, Arc<Site>>>,
+}
+
+struct Site {
+    limit: RateLimit,
+    permits: Option<Arc<Semaphore>>,
+    next: Mutex<Instant>,
+}
+
+/// Held while a request is in flight to count towards the concurrency limit
+pub struct RatePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// Apply the limit to every site
+    pub fn new(default: RateLimit) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                default,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Apply the limit to the site of the host in addition to the default limit
+    pub fn host(self, host: &str, limit: RateLimit) -> Self {
+        let site = site(host);
+        let mut configured = self.inner.configured.lock().unwrap();
+        configured.insert(site.clone(), limit);
+        drop(configured);
+
+        self.inner.sites.lock().unwrap().remove(&site);
+        self
+    }
+
+    /// Use the rate limit hinted by the extension for its base urls
+    pub fn hint(&self, meta: &Meta) {
+        let Some(limit) = &meta.rate_limit else {
+            return;
+        };
+
+        let mut hints = self.inner.hints.lock().unwrap();
+        let mut sites = self.inner.sites.lock().unwrap();
+        for url in &meta.base_urls {
+            if let Some(host) = Url::parse(url).ok().as_ref().and_then(Url::host_str) {
+                let site = site(host);
+                hints.insert(site.clone(), limit.clone());
+                sites.remove(&site);
+            }
+        }
+    }
+
+    /// The effective limit for the site of the host
+    pub fn limit(&self, host: &str) -> RateLimit {
+        self.site_limit(&site(host))
+    }
+
+    fn site_limit(&self, site: &str) -> RateLimit {
+        let mut limit = self.inner.default.clone();
+        if let Some(configured) = self.inner.configured.lock().unwrap().get(site) {
+            limit = limit.stricter(configured);
+        }
+        if let Some(hint) = self.inner.hints.lock().unwrap().get(site) {
+            limit = limit.stricter(hint);
+        }
+        limit
+    }
+
+    /// Wait until a request to the url is allowed by the limit of its site
+    pub async fn acquire(&self, url: &str) -> RatePermit {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            return RatePermit { _permit: None };
+        };
+
+        let state = self.site_state(&site(&host));
+
+        let permit = match &state.permits {
+            Some(permits) => Some(
+                Arc::clone(permits)
+                    .acquire_owned()
+                    .await
+                    .expect("the site semaphore is never closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(interval) = state.limit.interval {
+            let start = {
+                let mut next = state.next.lock().unwrap();
+                let start = (*next).max(Instant::now());
+                *next = start + Duration::from_millis(interval);
+                start
+            };
+
+            if start > Instant::now() {
+                debug!("delaying request to '{url}' by the site rate limit");
+                time::sleep_until(start).await;
+            }
+        }
+
+        if let Some(jitter) = state.limit.jitter {
+            time::sleep(Duration::from_millis(fastrand::u64(0..=jitter))).await;
+        }
+
+        RatePermit { _permit: permit }
+    }
+
+    fn site_state(&self, site: &str) -> Arc<Site> {
+        let limit = self.site_limit(site);
+        let mut sites = self.inner.sites.lock().unwrap();
+        let state = sites.entry(site.to_string()).or_insert_with(|| {
+            Arc::new(Site {
+                permits: limit.concurrency.map(|n| Arc::new(Semaphore::new(n))),
+                next: Mutex::new(Instant::now()),
+                limit,
+            })
+        });
+
+        Arc::clone(state)
+    }
+}
+
+/// The site the host belongs to, which is its registrable domain
+///
+/// Subdomains of one website usually share its servers, so they share a
+/// schedule too. Addresses and hosts without a registrable domain, such as
+/// `localhost`, are a site of their own.
+pub fn site(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+        return host;
+    }
+
+    match psl::domain_str(&host) {
+        Some(domain) => domain.to_string(),
+        None => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_space_requests_to_the_same_host() {
+        let limiter = RateLimiter::new(RateLimit {
+            interval: Some(50),
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire("https://example.com/page").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let start = Instant::now();
+        limiter.acquire("https://example.org").await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn should_share_schedule_between_subdomains() {
+        let limiter = RateLimiter::new(RateLimit {
+            interval: Some(50),
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        for url in [
+            "https://www.example.com",
+            "https://example.com",
+            "https://m.EXAMPLE.com",
+        ] {
+            limiter.acquire(url).await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let start = Instant::now();
+        limiter.acquire("http://127.0.0.1").await;
+        limiter.acquire("http://127.0.0.2").await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn should_group_hosts_by_registrable_domain() {
+        assert_eq!(site("www.example.com"), "example.com");
+        assert_eq!(site("chapters.example.co.uk."), "example.co.uk");
+        assert_eq!(site("novel.github.io"), "novel.github.io");
+        assert_eq!(site("localhost"), "localhost");
+        assert_eq!(site("127.0.0.1"), "127.0.0.1");
+        assert_eq!(site("[::1]"), "[::1]");
+    }
+
+    #[tokio::test]
+    async fn should_cap_concurrent_requests() {
+        let limiter = RateLimiter::default().host(
+            "example.com",
+            RateLimit {
+                concurrency: Some(1),
+                ..Default::default()
+            },
+        );
+
+        let permit = limiter.acquire("https://example.com/a").await;
+        let second = time::timeout(
+            Duration::from_millis(20),
+            limiter.acquire("https://example.com/b"),
+        )
+        .await;
+        assert!(second.is_err());
+
+        drop(permit);
+        let second = time::timeout(
+            Duration::from_millis(20),
+            limiter.acquire("https://example.com/b"),
+        )
+        .await;
+        assert!(second.is_ok());
+    }
+
+    #[test]
+    fn should_apply_meta_hint_to_base_urls() {
+        let limiter = RateLimiter::new(RateLimit {
+            interval: Some(100),
+            ..Default::default()
+        });
+
+        let meta = Meta {
+            base_urls: vec![String::from("https://www.example.com")],
+            rate_limit: Some(RateLimit {
+                interval: Some(10),
+                concurrency: Some(2),
+                jitter: None,
+            }),
+            ..Default::default()
+        };
+        limiter.hint(&meta);
+
+        let expected = RateLimit {
+            interval: Some(100),
+            concurrency: Some(2),
+            jitter: None,
+        };
+        assert_eq!(limiter.limit("www.example.com"), expected);
+        assert_eq!(limiter.limit("cdn.example.com"), expected);
+        assert_eq!(limiter.limit("example.org").concurrency, None);
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, error};
+use quelle_core::prelude::{Body, Method, Request, RequestError, Response};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    data::State,
+    error::{self, Error},
+    module::http::send_with,
+    network::NetworkPolicy,
+};
+
+const FIXTURE_EXTENSION: &str = "json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// Send every request with the runtime and write it with its response to the fixtures
+    Record,
+    /// Answer every request from the fixtures without touching the network
+    Replay,
+}
+
+/// A directory of recorded requests and their responses
+///
+/// A request is matched by its method, url, params and body. Headers are not
+/// part of the match, so cookies and user agents do not invalidate the fixtures.
+#[derive(Clone)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: ReplayMode,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    request: Request,
+    response: Result<Response, RequestError>,
+}
+
+/// The parts of a request the fixture is looked up by, in a stable order
+#[derive(Serialize)]
+struct Key<'a> {
+    method: &'a Method,
+    url: &'a str,
+    params: Vec<(&'a str, &'a str)>,
+    body: Option<KeyBody<'a>>,
+}
+
+#[derive(Serialize)]
+enum KeyBody<'a> {
+    Form(BTreeMap<&'a str, &'a str>),
+    UrlEncoded(BTreeMap<&'a str, &'a str>),
+    Json(&'a str),
+    Bytes {
+        content_type: &'a str,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Key<'a> {
+    fn new(request: &'a Request) -> Self {
+        let mut params = request
+            .params
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        params.sort();
+
+        let sorted = |map: &'a HashMap<String, String>| {
+            map.iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect()
+        };
+
+        let body = request.data.as_ref().map(|body| match body {
+            Body::Form(map) => KeyBody::Form(sorted(map)),
+            Body::UrlEncoded(map) => KeyBody::UrlEncoded(sorted(map)),
+            Body::Json(json) => KeyBody::Json(json),
+            Body::Bytes { content_type, data } => KeyBody::Bytes { content_type, data },
+        });
+
+        Key {
+            method: &request.method,
+            url: &request.url,
+            params,
+            body,
+        }
+    }
+
+    fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("request key is always serializable");
+        format!("{:x}", Sha256::digest(bytes))
+    }
+}
+
+impl Fixtures {
+    pub fn new<P: Into<PathBuf>>(dir: P, mode: ReplayMode) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    /// Record the requests of the extension into the directory
+    #[inline]
+    pub fn record<P: Into<PathBuf>>(dir: P) -> Self {
+        Self::new(dir, ReplayMode::Record)
+    }
+
+    /// Replay the requests of the extension from the directory
+    #[inline]
+    pub fn replay<P: Into<PathBuf>>(dir: P) -> Self {
+        Self::new(dir, ReplayMode::Replay)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn mode(&self) -> ReplayMode {
+        self.mode
+    }
+
+    /// The file the request is recorded in
+    pub fn path(&self, request: &Request) -> PathBuf {
+        self.dir
+            .join(Key::new(request).hash())
+            .with_extension(FIXTURE_EXTENSION)
+    }
+
+    /// Load the recorded response of the request
+    pub fn load(&self, request: &Request) -> error::Result<Result<Response, RequestError>> {
+        let path = self.path(request);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let description = format!(
+                    "{:?} '{}' (expected at '{}')",
+                    request.method,
+                    request.url,
+                    path.display()
+                );
+                error!("no fixture recorded for {description}");
+                return Err(Error::MissingFixture(description));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let fixture = serde_json::from_slice::<Fixture>(&bytes).map_err(|e| {
+            error!("failed to parse fixture '{}': {e}", path.display());
+            Error::DeserializeError
+        })?;
+        Ok(fixture.response)
+    }
+
+    /// Write the request with its response to the directory
+    pub fn save(
+        &self,
+        request: &Request,
+        response: Result<Response, RequestError>,
+    ) -> error::Result<Result<Response, RequestError>> {
+        fs::create_dir_all(&self.dir)?;
+
+        let path = self.path(request);
+        let fixture = Fixture {
+            request: request.clone(),
+            response,
+        };
+        let json = serde_json::to_vec_pretty(&fixture).map_err(|_| Error::SerializeError)?;
+        fs::write(&path, json)?;
+
+        debug!("recorded '{}' to '{}'", request.url, path.display());
+        Ok(fixture.response)
+    }
+
+    /// Answer the request from the fixtures, or send and record it in [ReplayMode::Record]
+    pub(crate) async fn respond<D>(
+        &self,
+        state: &State<D>,
+        network: &NetworkPolicy,
+        request: Request,
+    ) -> error::Result<Result<Response, RequestError>> {
+        match self.mode {
+            ReplayMode::Replay => self.load(&request),
+            ReplayMode::Record => {
+                let response = send_with(state, network, request.clone()).await;
+                self.save(&request, response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use quelle_core::prelude::RequestErrorKind;
+
+    use super::*;
+    use crate::{cookie::CookieJar, module::http::respond, Runtime, RuntimeBuilder};
+
+    fn response(url: &str) -> Response {
+        Response {
+            status: 200,
+            url: url.to_string(),
+            body: Some(b"<html></html>".to_vec()),
+            headers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn should_ignore_order_and_headers_in_key() {
+        let form = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let a = Request::post(String::from("https://example.com/search"))
+            .param("page", "1")
+            .param("q", "novel")
+            .form(form(&[("a", "1"), ("b", "2"), ("c", "3")]))
+            .header("User-Agent", "a");
+        let b = Request::post(String::from("https://example.com/search"))
+            .param("q", "novel")
+            .param("page", "1")
+            .form(form(&[("c", "3"), ("b", "2"), ("a", "1")]));
+        assert_eq!(Key::new(&a).hash(), Key::new(&b).hash());
+
+        let c = b
+            .clone()
+            .url_encoded(form(&[("a", "1"), ("b", "2"), ("c", "3")]));
+        let d = Request::get(String::from("https://example.com/search"))
+            .param("q", "novel")
+            .param("page", "1");
+        assert_ne!(Key::new(&b).hash(), Key::new(&c).hash());
+        assert_ne!(Key::new(&b).hash(), Key::new(&d).hash());
+    }
+
+    #[test]
+    fn should_replay_recorded_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = Fixtures::replay(dir.path());
+
+        let page = Request::get(String::from("https://example.com/novel"));
+        fixtures
+            .save(&page, Ok(response("https://example.com/novel")))
+            .unwrap()
+            .unwrap();
+
+        let missing = Request::get(String::from("https://example.com/gone"));
+        let error = RequestError {
+            kind: RequestErrorKind::Timeout,
+            url: Some(missing.url.clone()),
+            message: String::from("timed out"),
+        };
+        fixtures.save(&missing, Err(error)).unwrap().unwrap_err();
+
+        let replayed = fixtures.load(&page).unwrap().unwrap();
+        assert_eq!(replayed.url, "https://example.com/novel");
+        assert_eq!(replayed.body.as_deref(), Some(&b"<html></html>"[..]));
+
+        let replayed = fixtures.load(&missing).unwrap().unwrap_err();
+        assert!(matches!(replayed.kind, RequestErrorKind::Timeout));
+    }
+
+    #[test]
+    fn should_fail_on_missing_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = Fixtures::replay(dir.path());
+
+        let request = Request::get(String::from("https://example.com/novel")).param("page", "2");
+        match fixtures.load(&request) {
+            Err(Error::MissingFixture(description)) => {
+                assert!(description.contains("https://example.com/novel"))
+            }
+            _ => panic!("expected a missing fixture"),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_replay_without_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = Fixtures::replay(dir.path());
+
+        let page = Request::get(String::from("https://example.com/novel"));
+        let unavailable = Response {
+            status: 503,
+            ..response("https://example.com/novel")
+        };
+        fixtures.save(&page, Ok(unavailable)).unwrap().unwrap();
+
+        // Sent, the request would be blocked as the hosts of the extension are unknown
+        let state = RuntimeBuilder::default()
+            .fixtures(fixtures)
+            .state((), CookieJar::default());
+        let started = Instant::now();
+        let replayed = respond(&state, page).await.unwrap().unwrap();
+        assert_eq!(replayed.status, 503);
+        // Without retrying the unavailable response
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn should_fail_call_on_missing_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runtime = Runtime::builder()
+            .fixtures(Fixtures::replay(dir.path()))
+            .build(
+                &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/limits.wat"),
+                (),
+            )
+            .await
+            .unwrap();
+
+        let result = runtime.popular(1).await;
+        assert!(matches!(result, Err(Error::MissingFixture(_))));
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::{Duration, SystemTime};
+
+use quelle_core::prelude::{RequestError, RequestErrorKind, Response};
+
+/// How requests that failed with a transient error are retried
+///
+/// Timeouts, connection errors, `429 Too Many Requests` and `5xx` responses
+/// are retried with an exponential backoff. A `Retry-After` header takes the
+/// place of the backoff when the server sends one.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one
+    pub max_attempts: u32,
+
+    /// The delay before the first retry, doubled for every retry after it
+    pub base_delay: Duration,
+
+    /// The upper bound of a single delay, including the one from `Retry-After`
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send every request exactly once
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The delay before the next attempt, or `None` if the result is final
+    pub fn delay(&self, attempt: u32, result: &Result<Response, RequestError>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        match result {
+            Ok(response) if response.status == 429 || (500..600).contains(&response.status) => {
+                let delay = retry_after(response).unwrap_or(backoff);
+                Some(delay.min(self.max_delay))
+            }
+            Err(RequestError {
+                kind: RequestErrorKind::Timeout | RequestErrorKind::Connect,
+                ..
+            }) => Some(backoff),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the `Retry-After` header given either in seconds or as an http date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.header("retry-after")?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use quelle_core::prelude::HeaderMap;
+
+    use super::*;
+
+    fn response(status: usize, retry_after: Option<&str>) -> Result<Response, RequestError> {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = retry_after {
+            headers.append("Retry-After", value);
+        }
+
+        Ok(Response {
+            status,
+            url: String::from("https://example.com"),
+            body: None,
+            headers,
+        })
+    }
+
+    fn error(kind: RequestErrorKind) -> Result<Response, RequestError> {
+        Err(RequestError {
+            kind,
+            url: None,
+            message: String::new(),
+        })
+    }
+
+    #[test]
+    fn should_retry_transient_failures_with_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(3),
+        };
+
+        assert_eq!(
+            policy.delay(1, &response(503, None)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.delay(2, &error(RequestErrorKind::Timeout)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            policy.delay(3, &error(RequestErrorKind::Connect)),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(policy.delay(4, &response(503, None)), None);
+    }
+
+    #[test]
+    fn should_not_retry_final_results() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.delay(1, &response(200, None)), None);
+        assert_eq!(policy.delay(1, &response(404, None)), None);
+        assert_eq!(policy.delay(1, &error(RequestErrorKind::Body)), None);
+        assert_eq!(RetryPolicy::none().delay(1, &response(503, None)), None);
+    }
+
+    #[test]
+    fn should_honor_retry_after() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(
+            policy.delay(1, &response(429, Some("7"))),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            policy.delay(1, &response(429, Some("120"))),
+            Some(policy.max_delay)
+        );
+        assert_eq!(
+            policy.delay(1, &response(503, Some("Wed, 21 Oct 2015 07:28:00 GMT"))),
+            Some(Duration::ZERO)
+        );
+    }
+}
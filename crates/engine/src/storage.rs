@@ -0,0 +1,281 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use log::warn;
+use quelle_core::prelude::StorageError;
+
+use crate::cookie::extension_file;
+
+/// The sizes the values of a single extension are limited to, in bytes
+#[derive(Debug, Clone)]
+pub struct Quota {
+    pub max_key_size: usize,
+    pub max_value_size: usize,
+    /// The size of every key and value of the extension together
+    pub max_total_size: usize,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            max_key_size: 256,
+            max_value_size: 64 * 1024,
+            max_total_size: 1024 * 1024,
+        }
+    }
+}
+
+/// The key-value storage of every extension, keyed by the extension id
+///
+/// With a directory, the values of each extension are saved to `<dir>/<id>.json`
+/// whenever they change. The values of ids that are not valid file names are only
+/// kept in memory. Clones share the same values.
+#[derive(Clone, Default)]
+pub struct Storage {
+    dir: Option<PathBuf>,
+    quota: Quota,
+    namespaces: Arc<Mutex<HashMap<String, Namespace>>>,
+}
+
+impl Storage {
+    /// Keep the values in memory only
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Save the values to the directory so they outlive the process
+    pub fn persistent(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            ..Default::default()
+        }
+    }
+
+    /// Limit the size of the values of each extension
+    pub fn quota(mut self, quota: Quota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// The values of the extension with the id
+    pub fn namespace(&self, id: &str) -> Namespace {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let namespace = namespaces.entry(id.to_string()).or_insert_with(|| {
+            let path = self.dir.as_ref().and_then(|dir| extension_file(dir, id));
+            Namespace::open(path, self.quota.clone())
+        });
+
+        namespace.clone()
+    }
+}
+
+/// The values of a single extension
+#[derive(Clone)]
+pub struct Namespace {
+    inner: Arc<Mutex<NamespaceInner>>,
+    quota: Quota,
+}
+
+struct NamespaceInner {
+    values: BTreeMap<String, String>,
+    path: Option<PathBuf>,
+}
+
+impl Namespace {
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.inner.lock().unwrap().values.get(key).cloned()
+    }
+
+    /// Store the value under the key, unless it exceeds the quota
+    pub fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        if key.len() > self.quota.max_key_size {
+            return Err(StorageError::QuotaExceeded(format!(
+                "the key is larger than {} bytes",
+                self.quota.max_key_size
+            )));
+        }
+        if value.len() > self.quota.max_value_size {
+            return Err(StorageError::QuotaExceeded(format!(
+                "the value is larger than {} bytes",
+                self.quota.max_value_size
+            )));
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let replaced = inner.values.get(key).map_or(0, |old| key.len() + old.len());
+        if inner.size() - replaced + key.len() + value.len() > self.quota.max_total_size {
+            return Err(StorageError::QuotaExceeded(format!(
+                "the values are larger than {} bytes",
+                self.quota.max_total_size
+            )));
+        }
+
+        inner.values.insert(key.to_string(), value.to_string());
+        inner.save()
+    }
+
+    pub fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.values.remove(key).is_none() {
+            return Ok(());
+        }
+        inner.save()
+    }
+
+    /// Remove every value, including the saved ones
+    pub fn clear(&self) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.values.clear();
+        inner.save()
+    }
+
+    /// The size of every key and value together in bytes
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size()
+    }
+
+    /// Read the saved values, moving an unreadable file aside rather than losing it
+    fn open(path: Option<PathBuf>, quota: Quota) -> Self {
+        let values = path
+            .as_ref()
+            .and_then(|path| Some((path, fs::read(path).ok()?)))
+            .and_then(|(path, bytes)| match serde_json::from_slice(&bytes) {
+                Ok(values) => Some(values),
+                Err(e) => {
+                    let aside = path.with_extension("json.unreadable");
+                    warn!(
+                        "moved unreadable extension storage to '{}': {e}",
+                        aside.display()
+                    );
+                    let _ = fs::rename(path, aside);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            inner: Arc::new(Mutex::new(NamespaceInner { values, path })),
+            quota,
+        }
+    }
+}
+
+impl NamespaceInner {
+    fn size(&self) -> usize {
+        self.values
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum()
+    }
+
+    fn save(&self) -> Result<(), StorageError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write to a temporary file first, so a partial write never replaces the values
+        let json = serde_json::to_vec(&self.values).map_err(|_| StorageError::Serial)?;
+        let tmp = path.with_extension("json.tmp");
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp, json))
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| {
+                warn!(
+                    "failed to save extension storage to '{}': {e}",
+                    path.display()
+                );
+                StorageError::Io(e.to_string())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_namespaces_apart() {
+        let storage = Storage::new();
+        storage.namespace("en.a").set("token", "abc").unwrap();
+
+        assert_eq!(
+            storage.namespace("en.a").get("token").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(storage.namespace("en.b").get("token"), None);
+
+        storage.namespace("en.a").delete("token").unwrap();
+        assert_eq!(storage.namespace("en.a").get("token"), None);
+    }
+
+    #[test]
+    fn should_persist_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::persistent(dir.path().to_path_buf());
+        storage
+            .namespace("en.a")
+            .set("mirror", "https://a.com")
+            .unwrap();
+
+        let reopened = Storage::persistent(dir.path().to_path_buf());
+        assert_eq!(
+            reopened.namespace("en.a").get("mirror").as_deref(),
+            Some("https://a.com")
+        );
+        assert!(dir.path().join("en.a.json").exists());
+    }
+
+    #[test]
+    fn should_keep_unreadable_and_invalid_namespaces_out_of_the_way() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("en.a.json"), b"{\"token\":").unwrap();
+
+        let storage = Storage::persistent(dir.path().join("storage"));
+        storage.namespace("../escape").set("token", "abc").unwrap();
+        assert!(!dir.path().join("escape.json").exists());
+
+        let storage = Storage::persistent(dir.path().to_path_buf());
+        let namespace = storage.namespace("en.a");
+        assert_eq!(namespace.get("token"), None);
+        namespace.set("token", "abc").unwrap();
+
+        assert_eq!(
+            fs::read(dir.path().join("en.a.json.unreadable")).unwrap(),
+            b"{\"token\":"
+        );
+        assert!(!dir.path().join("en.a.json.tmp").exists());
+    }
+
+    #[test]
+    fn should_enforce_quota() {
+        let storage = Storage::new().quota(Quota {
+            max_key_size: 4,
+            max_value_size: 8,
+            max_total_size: 16,
+        });
+        let namespace = storage.namespace("en.a");
+
+        assert!(matches!(
+            namespace.set("long key", "v"),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert!(matches!(
+            namespace.set("k", "a long value"),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+
+        namespace.set("a", "1234567").unwrap();
+        // Replacing a value only counts the difference
+        namespace.set("a", "7654321").unwrap();
+        assert!(matches!(
+            namespace.set("b", "12345678"),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert_eq!(namespace.size(), 8);
+    }
+}
@@ -0,0 +1,38 @@
+;; A minimal extension used to exercise the runtime limits.
+;;
+;; `fetch_novel` never returns, `fetch_chapter_content` grows the
+;; linear memory by 64 MiB before returning an empty result and `popular`
+;; waits on a request to `http://127.0.0.1/`.
+(module
+  (import "env" "http_send_request" (func $send_request (param i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (func (export "abi_version") (result i32) (i32.const 1))
+  ;; a postcard encoded `GET http://127.0.0.1/`
+  (data (i32.const 16) "\00\11http://127.0.0.1/\00\00\00")
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "stack_push") (param i32))
+  (func (export "stack_pop") (result i32) (i32.const 0))
+  (func (export "last_result") (result i32) (i32.const 0))
+
+  (func (export "setup_default") (param i32))
+  (func (export "meta") (result i32) (i32.const 0))
+
+  (func (export "fetch_novel") (param i32) (result i32)
+    (loop $forever (br $forever))
+    (i32.const 0))
+
+  (func (export "fetch_chapter_content") (param i32) (result i32)
+    (drop (memory.grow (i32.const 1024)))
+    (i32.const 0))
+
+  (func (export "popular") (param i32) (result i32)
+    (call $send_request (i32.const 16) (i32.const 22))))
@@ -0,0 +1,42 @@
+;; A misbehaving extension used to exercise guest memory access.
+;;
+;; Every export hands the host a pointer or length that is out of bounds
+;; or points at invalid utf-8 (`\ff\fe` at offset 16).
+(module
+  (import "env" "io_print" (func $print (param i32 i32)))
+  (import "env" "http_send_request" (func $send_request (param i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (func (export "abi_version") (result i32) (i32.const 2))
+  (data (i32.const 16) "\ff\fe")
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "stack_push") (param i32))
+  (func (export "stack_pop") (result i32) (i32.const 0x7fffffff))
+  (func (export "last_result") (result i32) (i32.const 16))
+
+  (func (export "setup_default") (param i32))
+
+  ;; offset 65000 with a length of `stack_pop`
+  (func (export "meta") (result i32) (i32.const 65000))
+
+  ;; a result as long as the whole memory, starting at `last_result`
+  (func (export "fetch_novel") (param i32) (result i32) (i32.const 0x10000))
+
+  (func (export "fetch_chapter_content") (param i32) (result i32)
+    (call $print (i32.const -8) (i32.const 4))
+    (call $print (i32.const 16) (i32.const 2))
+    (i32.const 0))
+
+  (func (export "popular") (param i32) (result i32)
+    (call $send_request (i32.const 65530) (i32.const 100)))
+
+  ;; the two invalid utf-8 bytes at `last_result`
+  (func (export "text_search_url") (param i32 i32) (result i32) (i32.const 2)))
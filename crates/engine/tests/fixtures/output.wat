@@ -0,0 +1,41 @@
+;; A minimal extension used to exercise the output and log level hooks.
+;;
+;; `text_search_url` prints "hello" to stdout, traces "world", and prints
+;; the level last given to `set_level_filter` to stderr.
+(module
+  (import "env" "io_print" (func $print (param i32 i32)))
+  (import "env" "io_eprint" (func $eprint (param i32 i32)))
+  (import "env" "io_trace" (func $trace (param i32 i32)))
+
+  (memory (export "memory") 1)
+  (func (export "abi_version") (result i32) (i32.const 2))
+  (data (i32.const 16) "hello")
+  (data (i32.const 24) "world")
+  (global $heap (mut i32) (i32.const 1024))
+  (global $level (mut i32) (i32.const 0))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "stack_push") (param i32))
+  (func (export "stack_pop") (result i32) (i32.const 0))
+  (func (export "last_result") (result i32) (i32.const 0))
+
+  (func (export "setup_default") (param i32))
+  (func (export "set_level_filter") (param $level i32)
+    (global.set $level (local.get $level)))
+  (func (export "meta") (result i32) (i32.const 0))
+
+  (func (export "fetch_novel") (param i32) (result i32) (i32.const 0))
+  (func (export "fetch_chapter_content") (param i32) (result i32) (i32.const 0))
+
+  (func (export "text_search_url") (param i32 i32) (result i32)
+    (call $print (i32.const 16) (i32.const 5))
+    (call $trace (i32.const 24) (i32.const 5))
+    (i32.store8 (i32.const 32) (i32.add (i32.const 48) (global.get $level)))
+    (call $eprint (i32.const 32) (i32.const 1))
+    (i32.const 0)))
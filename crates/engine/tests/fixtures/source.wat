@@ -0,0 +1,44 @@
+;; The core module of an extension of the `source-extension` world
+;;
+;; The tests encode it into a component. `meta` returns a fixed meta,
+;; `fetch-chapter-content` returns the `utf8-error` and `fetch-novel` traps.
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 4096))
+
+  (data (i32.const 16) "en.test")
+  (data (i32.const 32) "Test")
+  (data (i32.const 48) "0.1.0")
+  (data (i32.const 64) "\60\00\00\00\18\00\00\00")
+  (data (i32.const 96) "https://test.example.com")
+
+  ;; The id, name, langs, version and base urls of the meta, the other fields are empty
+  (data (i32.const 256)
+    "\10\00\00\00\07\00\00\00"
+    "\20\00\00\00\04\00\00\00"
+    "\00\00\00\00\00\00\00\00"
+    "\30\00\00\00\05\00\00\00"
+    "\40\00\00\00\01\00\00\00")
+
+  ;; The error case of a result holding the `utf8-error` case
+  (data (i32.const 512) "\01\00\00\00\02")
+
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+    (local.get $ptr))
+
+  (func (export "quelle:extension/source@0.1.0#meta") (result i32)
+    (i32.const 256))
+
+  (func (export "quelle:extension/source@0.1.0#setup") (param i32 i32))
+
+  (func (export "quelle:extension/source@0.1.0#set-level-filter") (param i32 i32))
+
+  (func (export "quelle:extension/source@0.1.0#fetch-novel") (param i32 i32) (result i32)
+    unreachable)
+
+  (func (export "quelle:extension/source@0.1.0#fetch-chapter-content") (param i32 i32) (result i32)
+    (i32.const 512))
+)
@@ -0,0 +1,33 @@
+;; A minimal extension used to exercise recovery after a trap.
+;;
+;; `fetch_novel` always traps and `text_search_url` traps unless `setup`
+;; was called on the current instance.
+(module
+  (memory (export "memory") 1)
+  (func (export "abi_version") (result i32) (i32.const 2))
+  (global $heap (mut i32) (i32.const 1024))
+  (global $ready (mut i32) (i32.const 0))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+
+  (func (export "stack_push") (param i32))
+  (func (export "stack_pop") (result i32) (i32.const 0))
+  (func (export "last_result") (result i32) (i32.const 0))
+
+  (func (export "setup") (param i32)
+    (global.set $ready (i32.const 1)))
+  (func (export "setup_default") (param i32))
+  (func (export "meta") (result i32) (i32.const 0))
+
+  (func (export "fetch_novel") (param i32) (result i32)
+    (unreachable))
+  (func (export "fetch_chapter_content") (param i32) (result i32) (i32.const 0))
+
+  (func (export "text_search_url") (param i32 i32) (result i32)
+    (if (i32.eqz (global.get $ready)) (then (unreachable)))
+    (i32.const 0)))
@@ -37,6 +37,14 @@ impl FromWasmAbi for String {
     }
 }
 
+impl FromWasmAbi for Vec<u8> {
+    type Type = *mut u8;
+    fn from_wasm_abi(value: Self::Type) -> Self {
+        let len = stack_pop() as usize;
+        unsafe { Vec::from_raw_parts(value, len, len) }
+    }
+}
+
 impl ToWasmAbi for &str {
     type Type = *const u8;
     fn to_wasm_abi(self) -> Self::Type {
@@ -3,9 +3,11 @@ mod convert;
 mod primitive;
 mod result;
 mod stack;
+mod version;
 
 pub use alloc::*;
 pub use convert::*;
 pub use primitive::*;
 pub use result::*;
 pub use stack::*;
+pub use version::*;
@@ -1,4 +1,4 @@
-use quelle_core::prelude::{BasicNovel, Content, Novel, QuelleError};
+use quelle_core::prelude::{BasicNovel, Content, Novel, QuelleError, Request};
 use serde::Serialize;
 use std::{cell::RefCell, mem, ptr};
 
@@ -19,7 +19,7 @@ fn take_last_result() -> Option<Vec<u8>> {
     LAST_RESULT.with(|prev| prev.borrow_mut().take())
 }
 
-#[no_mangle]
+#[cfg_attr(not(feature = "component"), no_mangle)]
 pub extern "C" fn last_result() -> *mut u8 {
     let mut last_result = match take_last_result() {
         Some(r) => r,
@@ -116,3 +116,15 @@ impl ToWasmAbi for Result<Vec<BasicNovel>, QuelleError> {
         }
     }
 }
+
+impl ToWasmAbi for Result<Request, QuelleError> {
+    type Type = i32;
+
+    #[inline]
+    fn to_wasm_abi(self) -> Self::Type {
+        match self {
+            Ok(v) => store_serde(v, false),
+            Err(e) => store_error(e),
+        }
+    }
+}
@@ -11,7 +11,7 @@ static STACK: Mutex<Stack> = Mutex::new(Stack {
     top: 0,
 });
 
-#[no_mangle]
+#[cfg_attr(not(feature = "component"), no_mangle)]
 pub extern "C" fn stack_push(value: i32) {
     let mut stack = STACK.lock().unwrap();
     let top = stack.top;
@@ -19,7 +19,7 @@ pub extern "C" fn stack_push(value: i32) {
     stack.top += 1;
 }
 
-#[no_mangle]
+#[cfg_attr(not(feature = "component"), no_mangle)]
 pub extern "C" fn stack_pop() -> i32 {
     let mut stack = STACK.lock().unwrap();
     stack.top -= 1;
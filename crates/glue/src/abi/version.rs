@@ -0,0 +1,9 @@
+use quelle_core::abi::ABI_VERSION;
+
+/// The version of the ABI the extension was built against
+///
+/// Checked by the engine when the extension is loaded.
+#[cfg_attr(not(feature = "component"), no_mangle)]
+pub extern "C" fn abi_version() -> u32 {
+    ABI_VERSION
+}
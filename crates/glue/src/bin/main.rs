@@ -8,6 +8,7 @@ fn main() {
         params: None,
         data: None,
         headers: None,
+        timeout: None,
     })
     .unwrap();
 
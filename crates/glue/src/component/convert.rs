@@ -0,0 +1,297 @@
+//! Conversions between the types of `quelle_core` and the types of the WIT world
+
+use quelle_core::prelude as core;
+
+use super::{
+    host_log::Level,
+    storage::StorageError,
+    types::{
+        Attribute, BasicNovel, Body, BytesBody, Chapter, Content, Meta, Metadata, Method,
+        Namespace, Novel, NovelStatus, ParseError, QuelleError, RateLimit, ReadingDirection,
+        Request, RequestError, RequestErrorKind, Response, TaggedDateTime, Volume,
+    },
+};
+
+/// The meta is kept in a static, so it is converted from a reference
+impl From<&core::Meta> for Meta {
+    fn from(value: &core::Meta) -> Self {
+        Meta {
+            id: value.id.clone(),
+            name: value.name.clone(),
+            langs: value.langs.clone(),
+            version: value.version.clone(),
+            base_urls: value.base_urls.clone(),
+            rds: value.rds.iter().map(Into::into).collect(),
+            attrs: value.attrs.iter().map(Into::into).collect(),
+            rate_limit: value.rate_limit.clone().map(Into::into),
+            extra_hosts: value.extra_hosts.clone(),
+        }
+    }
+}
+
+impl From<&core::ReadingDirection> for ReadingDirection {
+    fn from(value: &core::ReadingDirection) -> Self {
+        match value {
+            core::ReadingDirection::Ltr => ReadingDirection::Ltr,
+            core::ReadingDirection::Rtl => ReadingDirection::Rtl,
+        }
+    }
+}
+
+impl From<&core::Attribute> for Attribute {
+    fn from(value: &core::Attribute) -> Self {
+        match value {
+            core::Attribute::Fanfiction => Attribute::Fanfiction,
+        }
+    }
+}
+
+impl From<core::RateLimit> for RateLimit {
+    fn from(value: core::RateLimit) -> Self {
+        RateLimit {
+            interval: value.interval,
+            concurrency: value.concurrency.map(|v| v as u32),
+            jitter: value.jitter,
+        }
+    }
+}
+
+impl From<core::Novel> for Novel {
+    fn from(value: core::Novel) -> Self {
+        Novel {
+            url: value.url,
+            authors: value.authors,
+            title: value.title,
+            cover: value.cover,
+            description: value.description,
+            volumes: value.volumes.into_iter().map(Into::into).collect(),
+            metadata: value.metadata.into_iter().map(Into::into).collect(),
+            status: value.status.into(),
+            langs: value.langs,
+        }
+    }
+}
+
+impl From<core::Volume> for Volume {
+    fn from(value: core::Volume) -> Self {
+        Volume {
+            index: value.index,
+            name: value.name,
+            chapters: value.chapters.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<core::Chapter> for Chapter {
+    fn from(value: core::Chapter) -> Self {
+        Chapter {
+            index: value.index,
+            title: value.title,
+            url: value.url,
+            updated_at: value.updated_at.map(Into::into),
+        }
+    }
+}
+
+impl From<core::TaggedDateTime> for TaggedDateTime {
+    fn from(value: core::TaggedDateTime) -> Self {
+        match value {
+            core::TaggedDateTime::Utc(date) => TaggedDateTime::Utc(date.to_rfc3339()),
+            core::TaggedDateTime::Local(date) => {
+                TaggedDateTime::Local(date.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            }
+        }
+    }
+}
+
+impl From<core::Metadata> for Metadata {
+    fn from(value: core::Metadata) -> Self {
+        Metadata {
+            name: value.name,
+            value: value.value,
+            ns: match value.ns {
+                core::Namespace::DC => Namespace::Dc,
+                core::Namespace::OPF => Namespace::Opf,
+            },
+            others: value.others.into_iter().collect(),
+        }
+    }
+}
+
+impl From<core::NovelStatus> for NovelStatus {
+    fn from(value: core::NovelStatus) -> Self {
+        match value {
+            core::NovelStatus::Ongoing => NovelStatus::Ongoing,
+            core::NovelStatus::Hiatus => NovelStatus::Hiatus,
+            core::NovelStatus::Completed => NovelStatus::Completed,
+            core::NovelStatus::Stub => NovelStatus::Stub,
+            core::NovelStatus::Dropped => NovelStatus::Dropped,
+            core::NovelStatus::Unknown => NovelStatus::Unknown,
+        }
+    }
+}
+
+impl From<core::BasicNovel> for BasicNovel {
+    fn from(value: core::BasicNovel) -> Self {
+        BasicNovel {
+            title: value.title,
+            cover: value.cover,
+            url: value.url,
+        }
+    }
+}
+
+impl From<core::Content> for Content {
+    fn from(value: core::Content) -> Self {
+        Content { data: value.data }
+    }
+}
+
+impl From<core::QuelleError> for QuelleError {
+    fn from(value: core::QuelleError) -> Self {
+        match value {
+            core::QuelleError::RequestFailed(error) => {
+                QuelleError::RequestFailed(core::RequestError::from(error).into())
+            }
+            core::QuelleError::FilterVerificationFailed(message) => {
+                QuelleError::FilterVerificationFailed(message)
+            }
+            core::QuelleError::Utf8Error => QuelleError::Utf8Error,
+            core::QuelleError::ParseFailed(error) => QuelleError::ParseFailed(error.into()),
+            core::QuelleError::WasmAbiError(message) => QuelleError::WasmAbiError(message),
+            core::QuelleError::DecodeFailed(message) => QuelleError::DecodeFailed(message),
+        }
+    }
+}
+
+impl From<core::ParseError> for ParseError {
+    fn from(value: core::ParseError) -> Self {
+        match value {
+            core::ParseError::ElementNotFound => ParseError::ElementNotFound,
+            core::ParseError::SerializeFailed => ParseError::SerializeFailed,
+            core::ParseError::FailedURLParse => ParseError::FailedUrlParse,
+            core::ParseError::FailedJsonParse => ParseError::FailedJsonParse,
+            core::ParseError::ParseIntError => ParseError::ParseIntError,
+            core::ParseError::Other(message) => ParseError::Other(message),
+        }
+    }
+}
+
+impl From<core::Request> for Request {
+    fn from(value: core::Request) -> Self {
+        Request {
+            method: match value.method {
+                core::Method::Get => Method::Get,
+                core::Method::Post => Method::Post,
+                core::Method::Put => Method::Put,
+                core::Method::Patch => Method::Patch,
+                core::Method::Delete => Method::Delete,
+            },
+            url: value.url,
+            params: value.params,
+            data: value.data.map(|body| match body {
+                core::Body::Form(data) => Body::Form(data.into_iter().collect()),
+                core::Body::UrlEncoded(data) => Body::UrlEncoded(data.into_iter().collect()),
+                core::Body::Json(data) => Body::Json(data),
+                core::Body::Bytes { content_type, data } => {
+                    Body::Bytes(BytesBody { content_type, data })
+                }
+            }),
+            headers: value.headers,
+            timeout: value.timeout,
+        }
+    }
+}
+
+impl From<Response> for core::Response {
+    fn from(value: Response) -> Self {
+        let mut headers = core::HeaderMap::new();
+        for (name, value) in value.headers {
+            headers.append(name, value);
+        }
+
+        core::Response {
+            status: value.status as usize,
+            url: value.url,
+            body: value.body,
+            headers,
+        }
+    }
+}
+
+impl From<core::RequestError> for RequestError {
+    fn from(value: core::RequestError) -> Self {
+        RequestError {
+            kind: match value.kind {
+                core::RequestErrorKind::Serial => RequestErrorKind::Serial,
+                core::RequestErrorKind::Request => RequestErrorKind::Request,
+                core::RequestErrorKind::Redirect => RequestErrorKind::Redirect,
+                core::RequestErrorKind::Status(status) => RequestErrorKind::Status(status),
+                core::RequestErrorKind::Body => RequestErrorKind::Body,
+                core::RequestErrorKind::Timeout => RequestErrorKind::Timeout,
+                core::RequestErrorKind::Unknown => RequestErrorKind::Unknown,
+                core::RequestErrorKind::Connect => RequestErrorKind::Connect,
+                core::RequestErrorKind::Blocked => RequestErrorKind::Blocked,
+                core::RequestErrorKind::TooLarge => RequestErrorKind::TooLarge,
+            },
+            url: value.url,
+            message: value.message,
+        }
+    }
+}
+
+impl From<RequestError> for core::RequestError {
+    fn from(value: RequestError) -> Self {
+        core::RequestError {
+            kind: match value.kind {
+                RequestErrorKind::Serial => core::RequestErrorKind::Serial,
+                RequestErrorKind::Request => core::RequestErrorKind::Request,
+                RequestErrorKind::Redirect => core::RequestErrorKind::Redirect,
+                RequestErrorKind::Status(status) => core::RequestErrorKind::Status(status),
+                RequestErrorKind::Body => core::RequestErrorKind::Body,
+                RequestErrorKind::Timeout => core::RequestErrorKind::Timeout,
+                RequestErrorKind::Unknown => core::RequestErrorKind::Unknown,
+                RequestErrorKind::Connect => core::RequestErrorKind::Connect,
+                RequestErrorKind::Blocked => core::RequestErrorKind::Blocked,
+                RequestErrorKind::TooLarge => core::RequestErrorKind::TooLarge,
+            },
+            url: value.url,
+            message: value.message,
+        }
+    }
+}
+
+impl From<StorageError> for core::StorageError {
+    fn from(value: StorageError) -> Self {
+        match value {
+            StorageError::Unavailable(message) => core::StorageError::Unavailable(message),
+            StorageError::QuotaExceeded(message) => core::StorageError::QuotaExceeded(message),
+            StorageError::Io(message) => core::StorageError::Io(message),
+            StorageError::Serial => core::StorageError::Serial,
+        }
+    }
+}
+
+impl From<Level> for log::Level {
+    fn from(value: Level) -> Self {
+        match value {
+            Level::Error => log::Level::Error,
+            Level::Warn => log::Level::Warn,
+            Level::Info => log::Level::Info,
+            Level::Debug => log::Level::Debug,
+            Level::Trace => log::Level::Trace,
+        }
+    }
+}
+
+impl From<log::Level> for Level {
+    fn from(value: log::Level) -> Self {
+        match value {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
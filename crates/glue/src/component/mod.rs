@@ -0,0 +1,147 @@
+//! Bindings of the WIT worlds in `wit/extension.wit`
+//!
+//! With the `component` feature, the `expose_*` macros export the interfaces of
+//! these worlds instead of the functions of the legacy ABI, and the http, log,
+//! output, time and storage functions of the glue call the imports of the world.
+//! Every exported interface has a world of its own, so an extension only
+//! exports the interfaces it implements.
+
+mod convert;
+
+use log::{LevelFilter, Record};
+use quelle_core::prelude::{ExtensionConfig, Request, RequestError, Response, StorageError};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{out::set_panic_hook, setup::init_extension};
+
+wit_bindgen::generate!({
+    path: "../../wit",
+    world: "imports",
+});
+
+use quelle::extension::{clock, http, log as host_log, storage};
+
+/// Generate the bindings of a world exporting a single interface
+///
+/// The types and imports are shared with the `imports` world, and the export
+/// macro is public so the `expose_*` macros can invoke it from the extension.
+macro_rules! export_world {
+    ($module:ident, $world:tt, $export_macro:tt) => {
+        pub mod $module {
+            wit_bindgen::generate!({
+                path: "../../wit",
+                world: $world,
+                with: {
+                    "quelle:extension/types@0.1.0": crate::component::quelle::extension::types,
+                    "quelle:extension/http@0.1.0": crate::component::quelle::extension::http,
+                    "quelle:extension/log@0.1.0": crate::component::quelle::extension::log,
+                    "quelle:extension/clock@0.1.0": crate::component::quelle::extension::clock,
+                    "quelle:extension/storage@0.1.0": crate::component::quelle::extension::storage,
+                },
+                pub_export_macro: true,
+                export_macro_name: $export_macro,
+            });
+        }
+    };
+}
+
+export_world!(source, "source-extension", "export_source");
+export_world!(popular, "popular-extension", "export_popular");
+export_world!(text_search, "text-search-extension", "export_text_search");
+export_world!(
+    filter_search,
+    "filter-search-extension",
+    "export_filter_search"
+);
+export_world!(image, "image-extension", "export_image");
+
+pub use filter_search::exports::quelle::extension::filter_search::Guest as FilterSearchGuest;
+pub use image::exports::quelle::extension::image::Guest as ImageGuest;
+pub use popular::exports::quelle::extension::popular::Guest as PopularGuest;
+pub use quelle::extension::{log::Level, types};
+pub use source::exports::quelle::extension::source::Guest as SourceGuest;
+pub use text_search::exports::quelle::extension::text_search::Guest as TextSearchGuest;
+
+/// Set up the extension with the level filter of the engine, see [init_extension]
+pub fn setup(level_filter: Option<host_log::Level>) {
+    #[cfg(debug_assertions)]
+    set_panic_hook();
+
+    init_extension(&ExtensionConfig {
+        level_filter: self::level_filter(level_filter),
+    });
+}
+
+/// Change the level of the logs sent to the engine without running the setup again
+pub fn set_level_filter(level_filter: Option<host_log::Level>) {
+    log::set_max_level(self::level_filter(level_filter));
+}
+
+fn level_filter(level: Option<host_log::Level>) -> LevelFilter {
+    level
+        .map(|level| log::Level::from(level).to_level_filter())
+        .unwrap_or(LevelFilter::Off)
+}
+
+/// The filter options as the json document of the `filter-search` interface
+pub fn filter_options<T: Serialize>(options: &T) -> String {
+    serde_json::to_string(options).unwrap_or_default()
+}
+
+/// Parse the json filter parameters of the `filter-search` interface
+pub fn filter_params<T: DeserializeOwned>(params: &str) -> Result<T, types::QuelleError> {
+    serde_json::from_str(params)
+        .map_err(|_| types::QuelleError::ParseFailed(types::ParseError::FailedJsonParse))
+}
+
+pub(crate) fn send_request(request: Request) -> Result<Response, RequestError> {
+    http::send_request(&request.into())
+        .map(Into::into)
+        .map_err(Into::into)
+}
+
+pub(crate) fn send_requests(requests: Vec<Request>) -> Vec<Result<Response, RequestError>> {
+    let requests = requests.into_iter().map(Into::into).collect::<Vec<_>>();
+    http::send_requests(&requests)
+        .into_iter()
+        .map(|response| response.map(Into::into).map_err(Into::into))
+        .collect()
+}
+
+pub(crate) fn log(record: &Record) {
+    host_log::log(&host_log::Event {
+        level: record.level().into(),
+        message: record.args().to_string(),
+        module_path: record.module_path().map(String::from),
+        file: record.file().map(String::from),
+        line: record.line(),
+    });
+}
+
+pub(crate) fn print(text: &str) {
+    host_log::print(text);
+}
+
+pub(crate) fn eprint(text: &str) {
+    host_log::eprint(text);
+}
+
+pub(crate) fn now() -> i64 {
+    clock::now()
+}
+
+pub(crate) fn utc_offset() -> i32 {
+    clock::utc_offset()
+}
+
+pub(crate) fn storage_get(key: &str) -> Result<Option<String>, StorageError> {
+    storage::get(key).map_err(Into::into)
+}
+
+pub(crate) fn storage_set(key: &str, value: &str) -> Result<(), StorageError> {
+    storage::set(key, value).map_err(Into::into)
+}
+
+pub(crate) fn storage_delete(key: &str) -> Result<(), StorageError> {
+    storage::delete(key).map_err(Into::into)
+}
@@ -1,13 +1,26 @@
+use std::ops::RangeInclusive;
+
 use quelle_core::prelude::*;
+#[cfg(not(feature = "component"))]
+use quelle_core::transport;
 
+#[cfg(not(feature = "component"))]
 use crate::prelude::FromWasmAbi;
 
+#[cfg(not(feature = "component"))]
 extern "C" {
     fn http_send_request(ptr: *const u8, len: u32) -> *mut u8;
+    fn http_send_requests(ptr: *const u8, len: u32) -> *mut u8;
 }
 
+#[cfg(feature = "component")]
 pub fn send_request(request: Request) -> Result<Response, BoxedRequestError> {
-    let req = serde_json::to_string(&request).map_err(|_| RequestError {
+    crate::component::send_request(request).map_err(|e| e.into())
+}
+
+#[cfg(not(feature = "component"))]
+pub fn send_request(request: Request) -> Result<Response, BoxedRequestError> {
+    let req = transport::encode(&request).map_err(|_| RequestError {
         kind: RequestErrorKind::Serial,
         url: Some(request.url.clone()),
         message: String::from("request serialization failed"),
@@ -15,9 +28,9 @@ pub fn send_request(request: Request) -> Result<Response, BoxedRequestError> {
 
     let resp = unsafe {
         let ptr = http_send_request(req.as_ptr(), req.len() as u32);
-        let resp = String::from_wasm_abi(ptr);
+        let resp = Vec::<u8>::from_wasm_abi(ptr);
 
-        let resp = serde_json::from_str::<Result<Response, RequestError>>(&resp).map_err(|_| {
+        let resp = transport::decode::<Result<Response, RequestError>>(&resp).map_err(|_| {
             RequestError {
                 kind: RequestErrorKind::Serial,
                 url: Some(request.url.clone()),
@@ -31,6 +44,70 @@ pub fn send_request(request: Request) -> Result<Response, BoxedRequestError> {
     resp.map_err(|e| e.into())
 }
 
+/// Send every request at once, letting the host run them concurrently
+///
+/// The responses are returned in the order of the requests.
+#[cfg(feature = "component")]
+pub fn send_requests(requests: Vec<Request>) -> Vec<Result<Response, BoxedRequestError>> {
+    crate::component::send_requests(requests)
+        .into_iter()
+        .map(|resp| resp.map_err(|e| e.into()))
+        .collect()
+}
+
+/// Send every request at once, letting the host run them concurrently
+///
+/// The responses are returned in the order of the requests.
+#[cfg(not(feature = "component"))]
+pub fn send_requests(requests: Vec<Request>) -> Vec<Result<Response, BoxedRequestError>> {
+    let serial_errors = |requests: &[Request], message: &str| {
+        requests
+            .iter()
+            .map(|request| {
+                Err(RequestError {
+                    kind: RequestErrorKind::Serial,
+                    url: Some(request.url.clone()),
+                    message: String::from(message),
+                }
+                .into())
+            })
+            .collect()
+    };
+
+    let Ok(req) = transport::encode(&requests) else {
+        return serial_errors(&requests, "request serialization failed");
+    };
+
+    let resp = unsafe {
+        let ptr = http_send_requests(req.as_ptr(), req.len() as u32);
+        Vec::<u8>::from_wasm_abi(ptr)
+    };
+
+    match transport::decode::<Vec<Result<Response, RequestError>>>(&resp) {
+        Ok(responses) if responses.len() == requests.len() => responses
+            .into_iter()
+            .map(|resp| resp.map_err(|e| e.into()))
+            .collect(),
+        _ => serial_errors(&requests, "response serialization failed"),
+    }
+}
+
+/// Fetch every page of a listing concurrently, returning the responses in page order
+///
+/// `request` builds the request of a page number. The first failed page is returned as
+/// the error.
+pub fn fetch_pages<F>(
+    pages: RangeInclusive<usize>,
+    request: F,
+) -> Result<Vec<Response>, BoxedRequestError>
+where
+    F: FnMut(usize) -> Request,
+{
+    send_requests(pages.map(request).collect())
+        .into_iter()
+        .collect()
+}
+
 pub trait SendRequest {
     fn send(self) -> Result<Response, BoxedRequestError>;
 }
@@ -1,4 +1,6 @@
 pub mod abi;
+#[cfg(feature = "component")]
+pub mod component;
 pub mod http;
 pub mod logger;
 pub mod macros;
@@ -6,4 +8,6 @@ pub mod node;
 pub mod out;
 pub mod prelude;
 pub mod setup;
+pub mod storage;
+pub mod time;
 pub mod traits;
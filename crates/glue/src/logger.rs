@@ -1,7 +1,9 @@
 use crate::out::println;
 use log::{LevelFilter, Log, Metadata, Record};
+#[cfg(not(feature = "component"))]
 use quelle_core::log::LogEvent;
 
+#[cfg(not(feature = "component"))]
 extern "C" {
     fn log_event(ptr: *const u8, len: usize);
 }
@@ -25,10 +27,17 @@ impl Logger {
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level().to_level_filter() <= self.filter
+        // The max level changes at runtime with `set_level_filter`
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &Record) {
+        #[cfg(feature = "component")]
+        if self.enabled(&record.metadata()) {
+            crate::component::log(record);
+        }
+
+        #[cfg(not(feature = "component"))]
         if self.enabled(&record.metadata()) {
             let bytes = serde_json::to_vec(&LogEvent::from(record)).unwrap();
             unsafe { log_event(bytes.as_ptr(), bytes.len()) };
@@ -8,6 +8,8 @@ macro_rules! define_meta {
             base_urls: [$($base_url:literal),+],
             rds: [$($rd:ident),+],
             attrs: [$($attr:ident),*],
+            $(rate_limit: $rate_limit:expr,)?
+            $(extra_hosts: [$($extra_host:literal),*],)?
         };
     ) => {
         static $var: once_cell::sync::Lazy<Meta> = once_cell::sync::Lazy::new(|| Meta {
@@ -18,9 +20,21 @@ macro_rules! define_meta {
             base_urls: vec![$(String::from($base_url)),+],
             rds: vec![$(ReadingDirection::$rd),+],
             attrs: vec![$(Attribute::$attr),*],
+            rate_limit: None$(.or(Some($rate_limit)))?,
+            extra_hosts: vec![$($(String::from($extra_host)),*)?],
         });
 
 
+        $crate::__expose_meta!($var);
+    };
+}
+
+/// Export the meta of [define_meta] to the legacy ABI
+#[cfg(not(feature = "component"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expose_meta {
+    ($var:ident) => {
         #[expose]
         pub fn meta() -> &'static Meta {
             &$var
@@ -28,4 +42,16 @@ macro_rules! define_meta {
     };
 }
 
+/// Keep the meta of [define_meta] for the `source` interface exported by `expose_basic`
+#[cfg(feature = "component")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __expose_meta {
+    ($var:ident) => {
+        pub fn meta() -> &'static Meta {
+            &$var
+        }
+    };
+}
+
 pub use define_meta;
@@ -3,12 +3,17 @@ use std::{
     panic,
 };
 
+#[cfg(not(feature = "component"))]
 extern "C" {
     fn io_print(ptr: *const u8, len: usize);
     fn io_eprint(ptr: *const u8, len: usize);
     fn io_trace(ptr: *const u8, len: usize);
 }
 
+#[cfg(feature = "component")]
+use crate::component::{eprint as _eprint, print as _print};
+
+#[cfg(not(feature = "component"))]
 #[inline]
 fn _print(buf: &str) {
     unsafe {
@@ -16,6 +21,7 @@ fn _print(buf: &str) {
     }
 }
 
+#[cfg(not(feature = "component"))]
 #[inline]
 fn _eprint(buf: &str) {
     unsafe {
@@ -83,6 +89,11 @@ pub fn set_panic_hook() {
 
         let err_info = format!("Panicked at '{}', {}:{}:{}", msg, file, line, col);
 
+        // The world has no trace import, which only adds a new line to stderr
+        #[cfg(feature = "component")]
+        _eprint(&format!("{err_info}\n"));
+
+        #[cfg(not(feature = "component"))]
         unsafe {
             io_trace(err_info.as_ptr(), err_info.len());
         }
@@ -5,6 +5,8 @@ pub use crate::macros::define_meta;
 pub use crate::node::*;
 pub use crate::out::set_panic_hook;
 pub use crate::setup::init_extension;
+pub use crate::storage;
+pub use crate::time;
 pub use crate::traits::*;
 
 // Re-export proc expose
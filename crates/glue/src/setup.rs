@@ -1,3 +1,4 @@
+use log::LevelFilter;
 use quelle_core::config::ExtensionConfig;
 
 use crate::{
@@ -11,7 +12,7 @@ use crate::{
 /// and applies the config
 ///
 /// See [init_extension]
-#[no_mangle]
+#[cfg_attr(not(feature = "component"), no_mangle)]
 pub fn setup_default(config: *mut u8) {
     #[cfg(debug_assertions)]
     set_panic_hook();
@@ -24,3 +25,17 @@ pub fn setup_default(config: *mut u8) {
 pub fn init_extension(config: &ExtensionConfig) {
     Logger::new(config.level_filter).init();
 }
+
+/// Change the level of the logs sent to the host without running the setup again
+#[cfg_attr(not(feature = "component"), no_mangle)]
+pub fn set_level_filter(level: i32) {
+    let level = match level {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    log::set_max_level(level);
+}
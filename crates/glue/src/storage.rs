@@ -0,0 +1,66 @@
+//! Values the engine keeps for the extension between calls and runs
+//!
+//! The storage is available once the engine read the meta of the extension,
+//! and every extension only sees its own values.
+
+use quelle_core::prelude::StorageError;
+#[cfg(not(feature = "component"))]
+use quelle_core::transport;
+#[cfg(not(feature = "component"))]
+use serde::de::DeserializeOwned;
+
+#[cfg(not(feature = "component"))]
+use crate::prelude::FromWasmAbi;
+
+#[cfg(not(feature = "component"))]
+extern "C" {
+    fn storage_get(ptr: *const u8, len: u32) -> *mut u8;
+    fn storage_set(ptr: *const u8, len: u32) -> *mut u8;
+    fn storage_delete(ptr: *const u8, len: u32) -> *mut u8;
+}
+
+/// The value stored under the key
+#[cfg(feature = "component")]
+pub fn get(key: &str) -> Result<Option<String>, StorageError> {
+    crate::component::storage_get(key)
+}
+
+/// Store the value under the key, replacing the previous one
+#[cfg(feature = "component")]
+pub fn set(key: &str, value: &str) -> Result<(), StorageError> {
+    crate::component::storage_set(key, value)
+}
+
+/// Remove the value stored under the key
+#[cfg(feature = "component")]
+pub fn delete(key: &str) -> Result<(), StorageError> {
+    crate::component::storage_delete(key)
+}
+
+/// The value stored under the key
+#[cfg(not(feature = "component"))]
+pub fn get(key: &str) -> Result<Option<String>, StorageError> {
+    let ptr = unsafe { storage_get(key.as_ptr(), key.len() as u32) };
+    decode(ptr)
+}
+
+/// Store the value under the key, replacing the previous one
+#[cfg(not(feature = "component"))]
+pub fn set(key: &str, value: &str) -> Result<(), StorageError> {
+    let args = transport::encode(&(key, value)).map_err(|_| StorageError::Serial)?;
+    let ptr = unsafe { storage_set(args.as_ptr(), args.len() as u32) };
+    decode(ptr)
+}
+
+/// Remove the value stored under the key
+#[cfg(not(feature = "component"))]
+pub fn delete(key: &str) -> Result<(), StorageError> {
+    let ptr = unsafe { storage_delete(key.as_ptr(), key.len() as u32) };
+    decode(ptr)
+}
+
+#[cfg(not(feature = "component"))]
+fn decode<T: DeserializeOwned>(ptr: *mut u8) -> Result<T, StorageError> {
+    let bytes = Vec::<u8>::from_wasm_abi(ptr);
+    transport::decode::<Result<T, StorageError>>(&bytes).map_err(|_| StorageError::Serial)?
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use quelle_core::prelude::TaggedDateTime;
+
+#[cfg(not(feature = "component"))]
+extern "C" {
+    fn time_now() -> i64;
+    fn time_utc_offset() -> i32;
+}
+
+/// The current time according to the engine
+pub fn now() -> DateTime<Utc> {
+    #[cfg(feature = "component")]
+    let millis = crate::component::now();
+    #[cfg(not(feature = "component"))]
+    let millis = unsafe { time_now() };
+    NaiveDateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .and_utc()
+}
+
+/// The offset of the timezone of the user
+pub fn utc_offset() -> FixedOffset {
+    #[cfg(feature = "component")]
+    let seconds = crate::component::utc_offset();
+    #[cfg(not(feature = "component"))]
+    let seconds = unsafe { time_utc_offset() };
+    FixedOffset::east_opt(seconds).unwrap_or(FixedOffset::east_opt(0).unwrap())
+}
+
+/// The current time in the timezone of the user
+pub fn local_now() -> DateTime<FixedOffset> {
+    now().with_timezone(&utc_offset())
+}
+
+/// Parse a date relative to the current time, like `3 hours ago`
+///
+/// See [quelle_core::time::parse_relative] for the accepted text.
+pub fn parse_relative(text: &str) -> Option<TaggedDateTime> {
+    quelle_core::time::parse_relative(text, now())
+}
@@ -22,6 +22,7 @@ pub trait FetchBasic {
 }
 
 /// The macro used to export [FetchBasic] to wasm abi
+#[cfg(not(feature = "component"))]
 #[macro_export]
 macro_rules! expose_basic {
     ($name:ident) => {
@@ -37,6 +38,49 @@ macro_rules! expose_basic {
     };
 }
 
+/// The macro used to export [FetchBasic] as an interface of the component
+///
+/// The `source` interface also holds the meta of [define_meta](crate::define_meta)
+/// and the setup, so the macro is invoked next to it.
+#[cfg(feature = "component")]
+#[macro_export]
+macro_rules! expose_basic {
+    ($name:ident) => {
+        impl $crate::component::SourceGuest for $name {
+            fn meta() -> $crate::component::types::Meta {
+                meta().into()
+            }
+
+            fn setup(level_filter: Option<$crate::component::Level>) {
+                $crate::component::setup(level_filter)
+            }
+
+            fn set_level_filter(level_filter: Option<$crate::component::Level>) {
+                $crate::component::set_level_filter(level_filter)
+            }
+
+            fn fetch_novel(
+                url: String,
+            ) -> Result<$crate::component::types::Novel, $crate::component::types::QuelleError> {
+                <$name as $crate::traits::FetchBasic>::fetch_novel(url)
+                    .map(Into::into)
+                    .map_err(Into::into)
+            }
+
+            fn fetch_chapter_content(
+                url: String,
+            ) -> Result<$crate::component::types::Content, $crate::component::types::QuelleError>
+            {
+                <$name as $crate::traits::FetchBasic>::fetch_chapter_content(url)
+                    .map(Into::into)
+                    .map_err(Into::into)
+            }
+        }
+
+        $crate::component::source::export_source!($name with_types_in $crate::component::source);
+    };
+}
+
 /// This trait adds popular search functionality to an extension/source
 ///
 /// The trait should be exposed to wasm abi using [`expose_popular`]
@@ -56,6 +100,7 @@ pub trait PopularSearch {
 }
 
 /// The macro used to export [PopularSearch] to wasm abi
+#[cfg(not(feature = "component"))]
 #[macro_export]
 macro_rules! expose_popular {
     ($name:ident) => {
@@ -71,6 +116,32 @@ macro_rules! expose_popular {
     };
 }
 
+/// The macro used to export [PopularSearch] as an interface of the component
+#[cfg(feature = "component")]
+#[macro_export]
+macro_rules! expose_popular {
+    ($name:ident) => {
+        impl $crate::component::PopularGuest for $name {
+            fn popular_url(page: i32) -> String {
+                <$name as $crate::traits::PopularSearch>::popular_url(page)
+            }
+
+            fn popular(
+                page: i32,
+            ) -> Result<
+                Vec<$crate::component::types::BasicNovel>,
+                $crate::component::types::QuelleError,
+            > {
+                <$name as $crate::traits::PopularSearch>::popular(page)
+                    .map(|novels| novels.into_iter().map(Into::into).collect())
+                    .map_err(Into::into)
+            }
+        }
+
+        $crate::component::popular::export_popular!($name with_types_in $crate::component::popular);
+    };
+}
+
 /// This trait adds text search functionality to an extension/source
 ///
 /// The trait should be exposed to wasm abi using [`expose_text`]
@@ -98,6 +169,7 @@ pub trait TextSearch {
 }
 
 /// The macro used to export [TextSearch] to wasm abi
+#[cfg(not(feature = "component"))]
 #[macro_export]
 macro_rules! expose_text {
     ($name:ident) => {
@@ -108,6 +180,39 @@ macro_rules! expose_text {
     };
 }
 
+/// The macro used to export [TextSearch] as an interface of the component
+#[cfg(feature = "component")]
+#[macro_export]
+macro_rules! expose_text {
+    ($name:ident) => {
+        impl $crate::component::TextSearchGuest for $name {
+            fn text_search_url(
+                query: String,
+                page: i32,
+            ) -> Result<String, $crate::component::types::QuelleError> {
+                <$name as $crate::traits::TextSearch>::text_search_url(query, page)
+                    .map_err(Into::into)
+            }
+
+            fn text_search(
+                query: String,
+                page: i32,
+            ) -> Result<
+                Vec<$crate::component::types::BasicNovel>,
+                $crate::component::types::QuelleError,
+            > {
+                <$name as $crate::traits::TextSearch>::text_search(query, page)
+                    .map(|novels| novels.into_iter().map(Into::into).collect())
+                    .map_err(Into::into)
+            }
+        }
+
+        $crate::component::text_search::export_text_search!(
+            $name with_types_in $crate::component::text_search
+        );
+    };
+}
+
 /// This trait adds filter search functionality to an extension/source
 ///
 /// The trait should be exposed to wasm abi using [`expose_filter`]
@@ -164,6 +269,7 @@ where
 }
 
 /// The macro used to export [FilterSearch] to wasm abi
+#[cfg(not(feature = "component"))]
 #[macro_export]
 macro_rules! expose_filter {
     ($name:ident) => {
@@ -189,3 +295,98 @@ macro_rules! expose_filter {
         }
     };
 }
+
+/// The macro used to export [FilterSearch] as an interface of the component
+#[cfg(feature = "component")]
+#[macro_export]
+macro_rules! expose_filter {
+    ($name:ident) => {
+        impl $crate::component::FilterSearchGuest for $name {
+            fn filter_options() -> String {
+                $crate::component::filter_options(
+                    <$name as $crate::traits::FilterSearch>::filter_options(),
+                )
+            }
+
+            fn filter_search_url(
+                params: String,
+                page: i32,
+            ) -> Result<String, $crate::component::types::QuelleError> {
+                let filter = $crate::component::filter_params(&params)?;
+                <$name as $crate::traits::FilterSearch>::filter_search_url(filter, page)
+                    .map_err(Into::into)
+            }
+
+            fn filter_search(
+                params: String,
+                page: i32,
+            ) -> Result<
+                Vec<$crate::component::types::BasicNovel>,
+                $crate::component::types::QuelleError,
+            > {
+                let filter = $crate::component::filter_params(&params)?;
+                <$name as $crate::traits::FilterSearch>::filter_search(filter, page)
+                    .map(|novels| novels.into_iter().map(Into::into).collect())
+                    .map_err(Into::into)
+            }
+        }
+
+        $crate::component::filter_search::export_filter_search!(
+            $name with_types_in $crate::component::filter_search
+        );
+    };
+}
+
+/// This trait lets an extension describe how the images of its source are fetched
+///
+/// Sources often refuse images without a `Referer` or a cookie, so the engine
+/// asks the extension for the request of each cover and inline image. Without
+/// the trait, the images are fetched with a plain `GET` request.
+///
+/// The trait should be exposed to wasm abi using [`expose_image`]
+///
+/// ## Example
+///
+/// ```ignore
+/// struct ExtensionName;
+/// expose_image!(ExtensionName);
+/// ```
+pub trait ImageRequest {
+    /// Construct the request fetching the image at the url
+    ///
+    /// The request is sent by the engine with the cookies and rate limit of the
+    /// extension, so it only needs to add what the source requires.
+    fn image_request(url: String) -> Result<Request, QuelleError>;
+}
+
+/// The macro used to export [ImageRequest] to wasm abi
+#[cfg(not(feature = "component"))]
+#[macro_export]
+macro_rules! expose_image {
+    ($name:ident) => {
+        #[quelle_glue::prelude::expose]
+        pub fn image_request(url: String) -> Result<Request, QuelleError> {
+            <$name as $crate::traits::ImageRequest>::image_request(url)
+        }
+    };
+}
+
+/// The macro used to export [ImageRequest] as an interface of the component
+#[cfg(feature = "component")]
+#[macro_export]
+macro_rules! expose_image {
+    ($name:ident) => {
+        impl $crate::component::ImageGuest for $name {
+            fn image_request(
+                url: String,
+            ) -> Result<$crate::component::types::Request, $crate::component::types::QuelleError>
+            {
+                <$name as $crate::traits::ImageRequest>::image_request(url)
+                    .map(Into::into)
+                    .map_err(Into::into)
+            }
+        }
+
+        $crate::component::image::export_image!($name with_types_in $crate::component::image);
+    };
+}
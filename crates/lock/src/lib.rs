@@ -8,7 +8,9 @@ use std::{
 
 use anyhow::{anyhow, bail, Context};
 use log::{debug, info};
-use quelle_engine::Runtime;
+use quelle_engine::{
+    any::AnyRuntime, cache::ModuleCache, capabilities::Capabilities, client::HttpConfig,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -24,6 +26,9 @@ pub struct Extension {
     pub base_urls: Vec<String>,
     pub langs: Vec<String>,
     pub path: PathBuf,
+    /// Missing from locks generated before capabilities were recorded
+    #[serde(default)]
+    pub capabilities: Option<Capabilities>,
 }
 
 impl Lock {
@@ -45,7 +50,13 @@ impl Lock {
         Ok(None)
     }
 
-    pub async fn generate(extensions_dir: &Path) -> anyhow::Result<Self> {
+    /// Read the meta of every extension in the directory
+    ///
+    /// With a module cache, the compiled extensions are reused by later runtimes.
+    pub async fn generate(
+        extensions_dir: &Path,
+        module_cache: Option<&ModuleCache>,
+    ) -> anyhow::Result<Self> {
         let mut extensions = HashMap::new();
 
         for entry in fs::read_dir(extensions_dir)? {
@@ -58,7 +69,7 @@ impl Lock {
             }
 
             info!("Reading meta info from '{}'...", path.display());
-            let mut runner = Runtime::new(&path)
+            let mut runner = AnyRuntime::new(&path, &HttpConfig::default(), module_cache.cloned())
                 .await
                 .map_err(|e| anyhow!(e.to_string()))?;
 
@@ -76,6 +87,7 @@ impl Lock {
                 base_urls: meta.base_urls,
                 langs: meta.langs,
                 path: entry.path(),
+                capabilities: Some(runner.capabilities().clone()),
             };
 
             extensions.insert(meta.id, extension);
@@ -12,6 +12,9 @@ use crate::{create_parent_all, error::PersistResult};
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Global {
     novels: HashMap<String, PathBuf>,
+    /// The base url of the mirror that answered last, by extension id
+    #[serde(default)]
+    mirrors: HashMap<String, String>,
 }
 
 impl Global {
@@ -59,6 +62,21 @@ impl Global {
     pub fn insert_novel(&mut self, url: String, path: PathBuf) {
         self.novels.insert(url, path);
     }
+
+    /// Keep finding the novel under its previous url after it moved to a mirror
+    pub fn alias_novel(&mut self, previous: String, url: &str) {
+        if let Some(path) = self.novels.get(url).cloned() {
+            self.novels.insert(previous, path);
+        }
+    }
+
+    pub fn mirror(&self, extension_id: &str) -> Option<&str> {
+        self.mirrors.get(extension_id).map(String::as_str)
+    }
+
+    pub fn set_mirror(&mut self, extension_id: String, base_url: String) {
+        self.mirrors.insert(extension_id, base_url);
+    }
 }
 
 #[cfg(test)]
@@ -6,7 +6,7 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
-use quelle_core::prelude::{Chapter, Novel};
+use quelle_core::prelude::{Chapter, Meta, Novel};
 use serde::{Deserialize, Serialize};
 
 use crate::{create_parent_all, error::PersistResult, event::EventLog, Event, EventKind, Persist};
@@ -93,6 +93,20 @@ impl<'a> PersistNovel<'a> {
         Ok(path)
     }
 
+    #[inline]
+    pub fn images_dir(&self) -> PathBuf {
+        self.dir.join("images")
+    }
+
+    /// Save an image the chapters refer to, creating the directory when missing
+    pub fn save_image(&self, name: &str, data: &[u8]) -> PersistResult<PathBuf> {
+        let path = self.images_dir().join(name);
+        create_parent_all(&path)?;
+
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+
     pub fn relative_path(&self, path: PathBuf) -> PathBuf {
         pathdiff::diff_paths(&path, &self.dir).unwrap_or(path)
     }
@@ -124,6 +138,41 @@ impl SavedNovel {
         }
     }
 
+    /// Point the stored urls to the mirror of the source at the base url
+    ///
+    /// Returns the novel url from before, when it changed.
+    pub fn rebase_urls(&mut self, meta: &Meta, base_url: &str) -> Option<String> {
+        let rebase = |url: &mut String| {
+            if meta.base_url_of(url) == Some(base_url) {
+                return;
+            }
+            if let Some(rebased) = meta.rebase_url(url, base_url) {
+                *url = rebased;
+            }
+        };
+
+        let previous = self.novel.url.clone();
+        rebase(&mut self.novel.url);
+        if let Some(cover) = self.novel.cover.as_mut() {
+            rebase(cover);
+        }
+
+        let chapters = self.novel.volumes.iter_mut().flat_map(|v| &mut v.chapters);
+        for chapter in chapters {
+            rebase(&mut chapter.url);
+        }
+
+        self.downloaded = std::mem::take(&mut self.downloaded)
+            .into_iter()
+            .map(|(mut url, path)| {
+                rebase(&mut url);
+                (url, path)
+            })
+            .collect();
+
+        (previous != self.novel.url).then_some(previous)
+    }
+
     pub fn commit_events(&mut self, events: Vec<Event>) {
         for event in events {
             match event.kind {
@@ -134,3 +183,53 @@ impl SavedNovel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quelle_core::prelude::Volume;
+
+    use super::*;
+
+    #[test]
+    fn should_rebase_urls_onto_mirror() {
+        let meta = Meta {
+            base_urls: vec![String::from("https://a.com"), String::from("https://b.com")],
+            ..Default::default()
+        };
+        let chapter = Chapter {
+            index: 1,
+            title: String::from("Chapter 1"),
+            url: String::from("https://a.com/novel/1"),
+            updated_at: None,
+        };
+        let mut data = SavedNovel::new(Novel {
+            url: String::from("https://a.com/novel"),
+            cover: Some(String::from("https://cdn.com/cover.png")),
+            volumes: vec![Volume {
+                index: 0,
+                name: String::from("Volume 1"),
+                chapters: vec![chapter],
+            }],
+            ..Default::default()
+        });
+        data.downloaded.insert(
+            String::from("https://a.com/novel/1"),
+            PathBuf::from("chapters/1.html"),
+        );
+
+        let previous = data.rebase_urls(&meta, "https://b.com");
+        assert_eq!(previous.as_deref(), Some("https://a.com/novel"));
+        assert_eq!(data.novel.url, "https://b.com/novel");
+        assert_eq!(
+            data.novel.cover.as_deref(),
+            Some("https://cdn.com/cover.png")
+        );
+        assert_eq!(
+            data.novel.volumes[0].chapters[0].url,
+            "https://b.com/novel/1"
+        );
+        assert!(data.downloaded.contains_key("https://b.com/novel/1"));
+
+        assert_eq!(data.rebase_urls(&meta, "https://b.com"), None);
+    }
+}
@@ -5,6 +5,10 @@ pub struct PersistOptions {
     pub base_dir: PathBuf,
     pub global_path: PathBuf,
     pub novel: NovelOptions,
+    /// The directory holding the cookie jar of each extension
+    pub cookies_dir: PathBuf,
+    /// The directory holding the key-value storage of each extension
+    pub storage_dir: PathBuf,
 }
 
 #[derive(Debug)]
@@ -31,6 +35,8 @@ impl Default for PersistOptions {
                 filename: PathBuf::from("novel.json"),
                 events: PathBuf::from("log.jsonl"),
             },
+            cookies_dir: base_dir.join("cookies"),
+            storage_dir: base_dir.join("storage"),
             base_dir,
         }
     }
@@ -27,7 +27,7 @@ expose_basic!(CreativeNovels);
 impl FetchBasic for CreativeNovels {
     fn fetch_novel(url: String) -> Result<Novel, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let author = doc
             .select_first(".x-bar-container > [class*='14']")
@@ -64,7 +64,7 @@ impl FetchBasic for CreativeNovels {
 
     fn fetch_chapter_content(url: String) -> Result<Content, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let content = doc
             .select_first("article .entry-content")
@@ -124,7 +124,7 @@ fn collect_volumes(doc: &NodeRef) -> Result<Vec<Volume>, QuelleError> {
     .form(form)
     .send()?;
 
-    let content = response.text()?.unwrap();
+    let content = response.decoded_text()?;
     if content.starts_with("success") {
         let content = &content["success.define.".len()..];
         for data in content.split(".end_data.") {
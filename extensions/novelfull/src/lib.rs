@@ -25,9 +25,9 @@ expose_basic!(NovelFull);
 impl FetchBasic for NovelFull {
     fn fetch_novel(url: String) -> Result<Novel, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let content = response.text()?.unwrap();
+        let content = response.decoded_text()?;
         let has_chapter_option_url = content.find("var ajaxChapterOptionUrl =").is_some();
-        let doc = kuchiki::parse_html().one(content);
+        let doc = kuchiki::parse_html().one(content.as_ref());
 
         let novel = Novel {
             title: doc.select_first(".title").get_text()?,
@@ -50,7 +50,7 @@ impl FetchBasic for NovelFull {
 
     fn fetch_chapter_content(url: String) -> Result<Content, QuelleError> {
         let response = Request::get(url).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let content = doc
             .select_first("#chr-content, #chapter-content")
@@ -116,7 +116,7 @@ fn volumes(
     };
 
     let response = Request::get(url.clone()).send()?;
-    let chapterlist_doc = kuchiki::parse_html().one(response.text()?.unwrap());
+    let chapterlist_doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
     let elements = chapterlist_doc.select("ul.list-chapter > li > a[href], select > option[value]");
     if let Ok(elements) = elements {
@@ -15,9 +15,9 @@ impl TextSearch for NovelFull {
     fn text_search(query: String, page: i32) -> Result<Vec<BasicNovel>, QuelleError> {
         // UNWRAP: the function does not return error
         let url = Self::text_search_url(query, page).unwrap();
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
-        parse_search(url, doc)
+        let response = Request::get(url).send()?;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
+        parse_search(response.url, doc)
     }
 }
 
@@ -29,9 +29,9 @@ impl PopularSearch for NovelFull {
 
     fn popular(page: i32) -> Result<Vec<BasicNovel>, QuelleError> {
         let url = Self::popular_url(page);
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
-        parse_search(url, doc)
+        let response = Request::get(url).send()?;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
+        parse_search(response.url, doc)
     }
 }
 
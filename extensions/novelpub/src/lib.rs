@@ -19,6 +19,11 @@ define_meta! {
         base_urls: ["https://www.novelpub.com"],
         rds: [Ltr],
         attrs: [],
+        rate_limit: RateLimit {
+            interval: Some(250),
+            concurrency: Some(2),
+            jitter: Some(100),
+        },
     };
 }
 
@@ -26,7 +31,7 @@ expose_basic!(NovelPub);
 impl FetchBasic for NovelPub {
     fn fetch_novel(url: String) -> Result<Novel, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let mut status = NovelStatus::default();
         if let Some(nodes) = doc.select(".header-stats span").ok() {
@@ -62,7 +67,7 @@ impl FetchBasic for NovelPub {
 
     fn fetch_chapter_content(url: String) -> Result<Content, QuelleError> {
         let response = Request::get(url).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let content = doc
             .select_first("#chapter-container")
@@ -118,7 +123,7 @@ fn collect_toc(url: &str) -> Result<Vec<Volume>, QuelleError> {
     // parse the first page
     let curl = toc_url(url, 1);
     let response = Request::get(curl).send()?;
-    let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+    let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
     extract_toc(&doc, &mut volume)?;
 
     // get page count
@@ -144,10 +149,9 @@ fn collect_toc(url: &str) -> Result<Vec<Volume>, QuelleError> {
             .trim()
             .parse::<usize>()?;
 
-        for page in 2..=end {
-            let curl = toc_url(url, page);
-            let response = Request::get(curl).send()?;
-            let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let responses = http::fetch_pages(2..=end, |page| Request::get(toc_url(url, page)))?;
+        for response in responses {
+            let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
             extract_toc(&doc, &mut volume)?;
         }
     }
@@ -215,8 +219,9 @@ impl PopularSearch for NovelPub {
 
     fn popular(page: i32) -> Result<Vec<BasicNovel>, QuelleError> {
         let url = Self::popular_url(page);
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let response = Request::get(url).send()?;
+        let url = &response.url;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let mut novels = vec![];
         if let Ok(elements) = doc.select(".novel-list > .novel-item") {
@@ -233,7 +238,7 @@ impl PopularSearch for NovelPub {
                         .as_node()
                         .select_first(".novel-cover img")
                         .get_attribute("data-src"),
-                    url: META.convert_into_absolute_url(novel_url, Some(&url))?,
+                    url: META.convert_into_absolute_url(novel_url, Some(url))?,
                 };
 
                 novels.push(novel);
@@ -243,3 +248,11 @@ impl PopularSearch for NovelPub {
         Ok(novels)
     }
 }
+
+expose_image!(NovelPub);
+impl ImageRequest for NovelPub {
+    fn image_request(url: String) -> Result<Request, QuelleError> {
+        // The image host refuses hotlinked requests
+        Ok(Request::get(url).header("Referer", "https://www.novelpub.com/"))
+    }
+}
@@ -22,6 +22,7 @@ define_meta! {
         base_urls: ["https://www.royalroad.com"],
         rds: [Ltr],
         attrs: [],
+        extra_hosts: ["royalroadcdn.com"],
     };
 }
 
@@ -29,7 +30,7 @@ expose_basic!(RoyalRoad);
 impl FetchBasic for RoyalRoad {
     fn fetch_novel(url: String) -> Result<Novel, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let volume = Volume {
             chapters: doc
@@ -73,7 +74,7 @@ impl FetchBasic for RoyalRoad {
 
     fn fetch_chapter_content(url: String) -> Result<Content, QuelleError> {
         let response = Request::get(url).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let content = doc
             .select_first(".chapter-content")
@@ -137,8 +138,9 @@ impl PopularSearch for RoyalRoad {
 
     fn popular(page: i32) -> Result<Vec<BasicNovel>, QuelleError> {
         let url = Self::popular_url(page);
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let response = Request::get(url).send()?;
+        let url = &response.url;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let mut novels = vec![];
         if let Ok(elements) = doc.select(".fiction-list-item") {
@@ -149,7 +151,7 @@ impl PopularSearch for RoyalRoad {
                 let novel = BasicNovel {
                     title: item.as_node().select_first(".fiction-title").get_text()?,
                     cover: item.as_node().select_first("img").get_attribute("src"),
-                    url: META.convert_into_absolute_url(novel_url, Some(&url))?,
+                    url: META.convert_into_absolute_url(novel_url, Some(url))?,
                 };
 
                 novels.push(novel);
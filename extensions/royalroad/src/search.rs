@@ -152,9 +152,9 @@ impl FilterSearch for RoyalRoad {
         page: i32,
     ) -> Result<Vec<BasicNovel>, QuelleError> {
         let url = Self::filter_search_url(filter, page)?;
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
-        parse_search(url, doc)
+        let response = Request::get(url).send()?;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
+        parse_search(response.url, doc)
     }
 }
 
@@ -167,9 +167,9 @@ impl TextSearch for RoyalRoad {
 
     fn text_search(query: String, page: i32) -> Result<Vec<BasicNovel>, QuelleError> {
         let url = Self::text_search_url(query, page).unwrap();
-        let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
-        parse_search(url, doc)
+        let response = Request::get(url).send()?;
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
+        parse_search(response.url, doc)
     }
 }
 
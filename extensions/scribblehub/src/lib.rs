@@ -4,7 +4,7 @@ extern crate quelle_glue;
 
 use std::collections::HashMap;
 
-use chrono::NaiveDateTime;
+use chrono::NaiveDate;
 use kuchiki::{traits::TendrilSink, NodeRef};
 use quelle_core::prelude::*;
 use quelle_glue::prelude::*;
@@ -26,7 +26,7 @@ expose_basic!(ScribbleHub);
 impl FetchBasic for ScribbleHub {
     fn fetch_novel(url: String) -> Result<Novel, QuelleError> {
         let response = Request::get(url.clone()).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let id = url
             .split("/")
@@ -53,7 +53,7 @@ impl FetchBasic for ScribbleHub {
 
     fn fetch_chapter_content(url: String) -> Result<Content, QuelleError> {
         let response = Request::get(url).send()?;
-        let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+        let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
 
         let content = doc
             .select_first("#chp_raw")
@@ -122,7 +122,7 @@ fn volumes(id: &str) -> Result<Vec<Volume>, QuelleError> {
     .form(data)
     .send()?;
 
-    let doc = kuchiki::parse_html().one(response.text()?.unwrap());
+    let doc = kuchiki::parse_html().one(response.decoded_text()?.as_ref());
     let mut volume = Volume::default();
 
     if let Ok(nodes) = doc.select("li.toc_w") {
@@ -130,16 +130,18 @@ fn volumes(id: &str) -> Result<Vec<Volume>, QuelleError> {
             let Ok(a) = node.as_node().select_first("a") else { continue };
             let Some(href) = a.get_attribute("href") else { continue };
 
-            let time = node
-                .as_node()
-                .select_first(".fic_date_pub")
-                .get_attribute("title");
-
-            // TODO: parse relative time
-            let updated_at = time
-                .map(|time| NaiveDateTime::parse_from_str(&time, "").ok())
-                .flatten()
-                .map(TaggedDateTime::Local);
+            let date = node.as_node().select_first(".fic_date_pub");
+
+            // Recent chapters show the time relative to now, older ones their date
+            let updated_at = date
+                .get_text()
+                .ok()
+                .and_then(|text| time::parse_relative(&text))
+                .or_else(|| {
+                    let title = date.get_attribute("title")?;
+                    let date = NaiveDate::parse_from_str(&title, "%b %d, %Y").ok()?;
+                    date.and_hms_opt(0, 0, 0).map(TaggedDateTime::Local)
+                });
 
             let chapter = Chapter {
                 index: volume.chapters.len() as i32,